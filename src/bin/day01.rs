@@ -1,59 +1,227 @@
-use std::path::Path;
+use std::{fmt::Display, ops::RangeInclusive, path::Path};
 
-use advent_of_code_2022::stream_file_blocks;
+use advent_of_code_2022::{
+    stream_file_blocks,
+    top_k::{top_k, top_k_by_key},
+};
 use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
 
 const INPUT: &str = "input/day01.txt";
 
-fn get_elf_calories_stream<P: AsRef<Path>>(input: P) -> Result<impl Iterator<Item=usize>> {
-    Ok(stream_file_blocks(input)?.map(|elf_list| {
-        elf_list.into_iter().map(|cal_count| {
-            cal_count.parse::<usize>().expect("Invalid input")
-        }).sum()
-    }))
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ElfInventory {
+    index: usize,
+    item_count: usize,
+    total: usize,
 }
 
-fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    Ok(get_elf_calories_stream(input)?.max().unwrap_or_default())
+fn get_elf_inventories<P: AsRef<Path>>(input: P) -> Result<impl Iterator<Item = ElfInventory>> {
+    Ok(stream_file_blocks(input)?
+        .enumerate()
+        .map(|(index, elf_list)| ElfInventory {
+            index,
+            item_count: elf_list.len(),
+            total: elf_list
+                .into_iter()
+                .map(|cal_count| cal_count.parse::<usize>().expect("Invalid input"))
+                .sum(),
+        }))
 }
 
-fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let mut current_max_values = Vec::new();
+fn get_elf_calories_stream<P: AsRef<Path>>(input: P) -> Result<impl Iterator<Item = usize>> {
+    Ok(get_elf_inventories(input)?.map(|elf| elf.total))
+}
+
+/// Statistics over all elves, keeping track of which elf carries what
+#[derive(Debug, Clone)]
+struct CalorieReport {
+    /// Elves in input order
+    elves: Vec<ElfInventory>,
+    /// Calorie totals in ascending order
+    sorted_totals: Vec<usize>,
+}
+
+impl CalorieReport {
+    fn new(elves: impl IntoIterator<Item = ElfInventory>) -> Self {
+        let elves = elves.into_iter().collect::<Vec<_>>();
+        let mut sorted_totals = elves.iter().map(|elf| elf.total).collect::<Vec<_>>();
+        sorted_totals.sort_unstable();
+        CalorieReport {
+            elves,
+            sorted_totals,
+        }
+    }
+
+    fn from_file<P: AsRef<Path>>(input: P) -> Result<Self> {
+        Ok(Self::new(get_elf_inventories(input)?))
+    }
+
+    /// The rank of an elf by carried calories, the elf carrying the most has rank 1.
+    /// Elves carrying the same amount share a rank.
+    fn rank(&self, elf_index: usize) -> Option<usize> {
+        let total = self.elves.get(elf_index)?.total;
+        Some(self.sorted_totals.len() - self.sorted_totals.partition_point(|t| *t <= total) + 1)
+    }
 
-    for calories in get_elf_calories_stream(input)? {
-        let insert_pos = match current_max_values.binary_search(&calories) {
-            Ok(pos) => pos,
-            Err(pos) => pos
+    fn median(&self) -> Option<f64> {
+        let n = self.sorted_totals.len();
+        if n == 0 {
+            None
+        } else if n % 2 == 1 {
+            Some(self.sorted_totals[n / 2] as f64)
+        } else {
+            Some((self.sorted_totals[n / 2 - 1] + self.sorted_totals[n / 2]) as f64 / 2.0)
+        }
+    }
+
+    /// Nearest-rank percentile, `p` is given in percent
+    fn percentile(&self, p: f64) -> Option<usize> {
+        let n = self.sorted_totals.len();
+        if n == 0 || !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let rank = ((p / 100.0) * n as f64).ceil() as usize;
+        Some(self.sorted_totals[rank.clamp(1, n) - 1])
+    }
+
+    /// Elf counts per calorie bucket, the buckets evenly split the range of observed totals and
+    /// the last one ends at the largest total. There are fewer buckets if the totals span fewer
+    /// calories than requested buckets.
+    fn histogram(&self, buckets: usize) -> Vec<(RangeInclusive<usize>, usize)> {
+        let (Some(&min), Some(&max)) = (self.sorted_totals.first(), self.sorted_totals.last())
+        else {
+            return Vec::new();
         };
-        if current_max_values.len() < 3 || insert_pos != 0 {
-            current_max_values.insert(insert_pos, calories);
-            if current_max_values.len() > 3 {
-                current_max_values.remove(0);
+        let bucket_width = (max - min).div_ceil(buckets.max(1)).max(1);
+        let buckets = ((max - min) / bucket_width + 1).min(buckets.max(1));
+        let mut counts = vec![0; buckets];
+        self.sorted_totals
+            .iter()
+            .for_each(|t| counts[((t - min) / bucket_width).min(buckets - 1)] += 1);
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let start = min + i * bucket_width;
+                let end = if i + 1 == buckets {
+                    max
+                } else {
+                    start + bucket_width - 1
+                };
+                (start..=end, count)
+            })
+            .collect()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        let elves = self
+            .elves
+            .iter()
+            .map(|elf| json!({ "elf": elf, "rank": self.rank(elf.index) }))
+            .collect::<Vec<_>>();
+        let percentiles = REPORTED_PERCENTILES
+            .iter()
+            .map(|p| (p.to_string(), json!(self.percentile(*p as f64))))
+            .collect::<serde_json::Map<_, _>>();
+        let histogram = self
+            .histogram(HISTOGRAM_BUCKETS)
+            .into_iter()
+            .map(|(range, count)| json!({ "from": range.start(), "to": range.end(), "count": count }))
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::to_string_pretty(&json!({
+            "elves": elves,
+            "total": self.sorted_totals.iter().sum::<usize>(),
+            "median": self.median(),
+            "percentiles": percentiles,
+            "histogram": histogram,
+        }))?)
+    }
+}
+
+const REPORTED_PERCENTILES: [usize; 4] = [25, 50, 75, 90];
+const HISTOGRAM_BUCKETS: usize = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+impl Display for CalorieReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Elves: {}", self.elves.len())?;
+        writeln!(
+            f,
+            "Total calories: {}",
+            self.sorted_totals.iter().sum::<usize>()
+        )?;
+        if let Some(median) = self.median() {
+            writeln!(f, "Median: {}", median)?;
+        }
+        for p in REPORTED_PERCENTILES {
+            if let Some(value) = self.percentile(p as f64) {
+                writeln!(f, "P{}: {}", p, value)?;
             }
         }
+
+        writeln!(f, "\nTop elves:")?;
+        for elf in top_k_by_key(self.elves.iter(), 3, |elf| elf.total) {
+            writeln!(
+                f,
+                "  #{} elf {} carries {} calories in {} items",
+                self.rank(elf.index).unwrap(),
+                elf.index,
+                elf.total,
+                elf.item_count
+            )?;
+        }
+
+        writeln!(f, "\nHistogram:")?;
+        let histogram = self.histogram(HISTOGRAM_BUCKETS);
+        let largest_bucket = histogram.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+        for (range, count) in histogram {
+            writeln!(
+                f,
+                "  {:>8}..={:<8} | {:>4} {}",
+                range.start(),
+                range.end(),
+                count,
+                "#".repeat(count * HISTOGRAM_WIDTH / largest_bucket)
+            )?;
+        }
+        Ok(())
     }
+}
+
+fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
+    Ok(get_elf_calories_stream(input)?.max().unwrap_or_default())
+}
 
-    Ok(current_max_values.into_iter().sum())
+fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
+    Ok(top_k(get_elf_calories_stream(input)?, 3).into_iter().sum())
 }
 
 fn main() -> Result<()> {
-    println!("Answer for part 1: {}", part1(INPUT)?);
-    println!("Answer for part 2: {}", part2(INPUT)?);
+    match std::env::args().nth(1).as_deref() {
+        Some("report") => print!("{}", CalorieReport::from_file(INPUT)?),
+        Some("report-json") => println!("{}", CalorieReport::from_file(INPUT)?.to_json()?),
+        _ => {
+            println!("Answer for part 1: {}", part1(INPUT)?);
+            println!("Answer for part 2: {}", part2(INPUT)?);
+        }
+    }
 
     Ok(())
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use advent_of_code_2022::test_helpers::create_example_file;
     use indoc::indoc;
 
-
     #[test]
     fn test_d01_examples() {
-        let (dir, file) = create_example_file(indoc!{"
+        let (dir, file) = create_example_file(
+            indoc! {"
             1000
             2000
             3000
@@ -68,9 +236,67 @@ mod tests {
             9000
 
             10000
-        "}, None);
+        "},
+            None,
+        );
         assert_eq!(part1(&file).unwrap(), 24000);
         assert_eq!(part2(&file).unwrap(), 45000);
         drop(dir);
     }
+
+    #[test]
+    fn test_d01_report() {
+        let (dir, file) = create_example_file(
+            indoc! {"
+                1000
+                2000
+                3000
+
+                4000
+
+                5000
+                6000
+
+                7000
+                8000
+                9000
+
+                10000
+            "},
+            None,
+        );
+        let report = CalorieReport::from_file(&file).unwrap();
+        assert_eq!(report.elves[2].item_count, 2);
+        assert_eq!(report.rank(3), Some(1));
+        assert_eq!(report.rank(1), Some(5));
+        assert_eq!(report.rank(5), None);
+        assert_eq!(report.median(), Some(10000.0));
+        assert_eq!(report.percentile(50.0), Some(10000));
+        assert_eq!(report.percentile(100.0), Some(24000));
+        assert_eq!(report.percentile(0.0), Some(4000));
+        assert_eq!(
+            report.histogram(2),
+            vec![(4000..=13999, 4), (14000..=24000, 1)]
+        );
+        assert_eq!(
+            report.histogram(5),
+            vec![
+                (4000..=7999, 2),
+                (8000..=11999, 2),
+                (12000..=15999, 0),
+                (16000..=19999, 0),
+                (20000..=24000, 1)
+            ]
+        );
+        let narrow = CalorieReport::new([5, 6].map(|total| ElfInventory {
+            index: total,
+            item_count: 1,
+            total,
+        }));
+        assert_eq!(narrow.histogram(5), vec![(5..=5, 1), (6..=6, 1)]);
+        let json = serde_json::from_str::<serde_json::Value>(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["elves"][3]["rank"], 1);
+        assert_eq!(json["percentiles"]["90"], 24000);
+        drop(dir);
+    }
 }
@@ -1,5 +1,6 @@
 use advent_of_code_2022::stream_items_from_file;
-use std::{path::Path, str::FromStr};
+use itertools::Itertools;
+use std::{collections::HashMap, fmt::Display, path::Path};
 use thiserror::Error;
 
 use anyhow::Result;
@@ -7,6 +8,7 @@ use anyhow::Result;
 const INPUT: &str = "input/day02.txt";
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 enum RockPaperScissorsError {
     #[error("Invalid shape symbol '{0}'")]
     InvalidShapeSymbol(String),
@@ -14,147 +16,378 @@ enum RockPaperScissorsError {
     InvalidStrategyDescriptor(String),
     #[error("Invalid Game goal symbol '{0}'")]
     InvalidGameGoalSymbol(String),
+    #[error("Guide uses {symbols} different symbols, but there are only {shapes} shapes")]
+    TooManySymbols { symbols: usize, shapes: usize },
 }
 
+/// A shape is identified by its position in the cycle of its `Rules`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct Shape(usize);
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
+enum GameGoal {
+    Lose,
+    Draw,
+    Win,
 }
 
-impl Shape {
-    fn base_score(&self) -> usize {
+impl GameGoal {
+    /// Position of the outcome in a table ordered loss, draw, win
+    fn index(self) -> usize {
         match self {
-            Shape::Rock => 1,
-            Shape::Paper => 2,
-            Shape::Scissors => 3,
+            GameGoal::Lose => 0,
+            GameGoal::Draw => 1,
+            GameGoal::Win => 2,
         }
     }
+}
 
-    fn can_beat(&self, other: &Self) -> bool {
-        match self {
-            Shape::Rock => other == &Shape::Scissors,
-            Shape::Paper => other == &Shape::Rock,
-            Shape::Scissors => other == &Shape::Paper,
+/// Rules for a cyclic game like Rock-Paper-Scissors with an odd number of shapes.
+/// Every shape beats the `(n - 1) / 2` shapes directly before it in the cycle and is beaten by
+/// the ones directly after it.
+#[derive(Debug, Clone)]
+struct Rules {
+    shape_names: Vec<String>,
+    shape_scores: Vec<usize>,
+    /// Scores for losing, drawing and winning a round
+    outcome_scores: [usize; 3],
+    opponent_symbols: HashMap<String, Shape>,
+    own_symbols: HashMap<String, Shape>,
+    goal_symbols: HashMap<String, GameGoal>,
+}
+
+impl Rules {
+    /// Create rules for the given cycle of shapes.
+    /// Shapes score 1, 2, 3, ... in the given order and rounds are scored 0/3/6 for a loss, draw
+    /// and win. No symbols are defined yet.
+    fn new(cycle: &[&str]) -> Self {
+        assert!(
+            cycle.len() % 2 == 1,
+            "Cyclic games need an odd number of shapes"
+        );
+        Rules {
+            shape_names: cycle.iter().map(|s| s.to_string()).collect(),
+            shape_scores: (1..=cycle.len()).collect(),
+            outcome_scores: [0, 3, 6],
+            opponent_symbols: HashMap::new(),
+            own_symbols: HashMap::new(),
+            goal_symbols: HashMap::new(),
         }
     }
 
-    fn beaten_by(&self) -> Self {
-        match self {
-            Shape::Rock => Shape::Paper,
-            Shape::Paper => Shape::Scissors,
-            Shape::Scissors => Shape::Rock,
+    fn shape(&self, name: &str) -> Shape {
+        Shape(
+            self.shape_names
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or_else(|| panic!("Unknown shape {}", name)),
+        )
+    }
+
+    fn with_shape_scores(mut self, scores: &[(&str, usize)]) -> Self {
+        for (name, score) in scores {
+            let shape = self.shape(name);
+            self.shape_scores[shape.0] = *score;
         }
+        self
     }
 
-    fn beats(&self) -> Self {
-        match self {
-            Shape::Rock => Shape::Scissors,
-            Shape::Paper => Shape::Rock,
-            Shape::Scissors => Shape::Paper,
+    fn with_outcome_scores(mut self, scores: &[(GameGoal, usize)]) -> Self {
+        for (goal, score) in scores {
+            self.outcome_scores[goal.index()] = *score;
         }
+        self
     }
-}
 
-impl FromStr for Shape {
-    type Err = RockPaperScissorsError;
+    fn with_opponent_symbols(mut self, symbols: &[(&str, &str)]) -> Self {
+        for (symbol, name) in symbols {
+            let shape = self.shape(name);
+            self.opponent_symbols.insert(symbol.to_string(), shape);
+        }
+        self
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(Shape::Rock),
-            "B" | "Y" => Ok(Shape::Paper),
-            "C" | "Z" => Ok(Shape::Scissors),
-            _ => Err(RockPaperScissorsError::InvalidShapeSymbol(s.to_string())),
+    fn with_own_symbols(mut self, symbols: &[(&str, &str)]) -> Self {
+        for (symbol, name) in symbols {
+            let shape = self.shape(name);
+            self.own_symbols.insert(symbol.to_string(), shape);
         }
+        self
     }
-}
 
-struct GamePrediction(Shape, Shape);
+    fn with_goal_symbols(mut self, symbols: &[(&str, GameGoal)]) -> Self {
+        self.goal_symbols
+            .extend(symbols.iter().map(|(s, g)| (s.to_string(), *g)));
+        self
+    }
 
-impl FromStr for GamePrediction {
-    type Err = RockPaperScissorsError;
+    /// The puzzle's game: `A`/`B`/`C` and `X`/`Y`/`Z` both encode Rock, Paper and Scissors, or
+    /// `X`/`Y`/`Z` encode losing, drawing and winning.
+    fn rock_paper_scissors() -> Self {
+        Rules::new(&["Rock", "Paper", "Scissors"])
+            .with_outcome_scores(&[(GameGoal::Lose, 0), (GameGoal::Draw, 3), (GameGoal::Win, 6)])
+            .with_opponent_symbols(&[("A", "Rock"), ("B", "Paper"), ("C", "Scissors")])
+            .with_own_symbols(&[("X", "Rock"), ("Y", "Paper"), ("Z", "Scissors")])
+            .with_goal_symbols(&[
+                ("X", GameGoal::Lose),
+                ("Y", GameGoal::Draw),
+                ("Z", GameGoal::Win),
+            ])
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split_once(" ")
-            .ok_or_else(|| RockPaperScissorsError::InvalidStrategyDescriptor(s.to_string()))
-            .and_then(|(opponent, me)| Ok(GamePrediction(opponent.parse()?, me.parse()?)))
+    /// The five shape variant, opponent shapes use `A` to `E` and own shapes `V` to `Z` in the
+    /// order Rock, Paper, Scissors, Lizard, Spock.
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        let names = ["Rock", "Paper", "Scissors", "Lizard", "Spock"];
+        let opponent = ["A", "B", "C", "D", "E"].into_iter().zip(names);
+        let own = ["V", "W", "X", "Y", "Z"].into_iter().zip(names);
+        Rules::new(&["Rock", "Spock", "Paper", "Lizard", "Scissors"])
+            .with_shape_scores(&names.into_iter().zip(1..).collect::<Vec<_>>())
+            .with_opponent_symbols(&opponent.collect::<Vec<_>>())
+            .with_own_symbols(&own.collect::<Vec<_>>())
+            .with_goal_symbols(&[
+                ("X", GameGoal::Lose),
+                ("Y", GameGoal::Draw),
+                ("Z", GameGoal::Win),
+            ])
     }
-}
 
-impl GamePrediction {
-    fn outcome_score(&self) -> usize {
-        if self.0 == self.1 {
-            3
-        } else if self.1.can_beat(&self.0) {
-            6
-        } else {
-            0
+    fn shape_count(&self) -> usize {
+        self.shape_names.len()
+    }
+
+    fn outcome(&self, own: Shape, opponent: Shape) -> GameGoal {
+        let n = self.shape_count();
+        match (own.0 + n - opponent.0) % n {
+            0 => GameGoal::Draw,
+            d if d <= n / 2 => GameGoal::Win,
+            _ => GameGoal::Lose,
         }
     }
 
-    fn score(&self) -> usize {
-        self.1.base_score() + self.outcome_score()
+    /// The shape that achieves `goal` against `opponent`, picking the closest one in the cycle if
+    /// several shapes would do
+    fn shape_for_goal(&self, opponent: Shape, goal: GameGoal) -> Shape {
+        let n = self.shape_count();
+        match goal {
+            GameGoal::Lose => Shape((opponent.0 + n - 1) % n),
+            GameGoal::Draw => opponent,
+            GameGoal::Win => Shape((opponent.0 + 1) % n),
+        }
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum GameGoal {
-    Lose,
-    Draw,
-    Win,
-}
+    fn score(&self, game: &GamePrediction) -> usize {
+        self.shape_scores[game.1 .0] + self.outcome_scores[self.outcome(game.1, game.0).index()]
+    }
 
-impl FromStr for GameGoal {
-    type Err = RockPaperScissorsError;
+    fn parse_symbol<T: Copy>(
+        table: &HashMap<String, T>,
+        symbol: &str,
+        error: fn(String) -> RockPaperScissorsError,
+    ) -> Result<T, RockPaperScissorsError> {
+        table
+            .get(symbol)
+            .copied()
+            .ok_or_else(|| error(symbol.to_string()))
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(GameGoal::Lose),
-            "Y" => Ok(GameGoal::Draw),
-            "Z" => Ok(GameGoal::Win),
-            _ => Err(RockPaperScissorsError::InvalidGameGoalSymbol(s.to_string())),
-        }
+    fn parse_prediction(&self, s: &str) -> Result<GamePrediction, RockPaperScissorsError> {
+        let (opponent, me) = s
+            .split_once(' ')
+            .ok_or_else(|| RockPaperScissorsError::InvalidStrategyDescriptor(s.to_string()))?;
+        Ok(GamePrediction(
+            Self::parse_symbol(
+                &self.opponent_symbols,
+                opponent,
+                RockPaperScissorsError::InvalidShapeSymbol,
+            )?,
+            Self::parse_symbol(
+                &self.own_symbols,
+                me,
+                RockPaperScissorsError::InvalidShapeSymbol,
+            )?,
+        ))
+    }
+
+    fn parse_strategy(&self, s: &str) -> Result<Strategy, RockPaperScissorsError> {
+        let (opponent, goal) = s
+            .split_once(' ')
+            .ok_or_else(|| RockPaperScissorsError::InvalidStrategyDescriptor(s.to_string()))?;
+        Ok(Strategy(
+            Self::parse_symbol(
+                &self.opponent_symbols,
+                opponent,
+                RockPaperScissorsError::InvalidShapeSymbol,
+            )?,
+            Self::parse_symbol(
+                &self.goal_symbols,
+                goal,
+                RockPaperScissorsError::InvalidGameGoalSymbol,
+            )?,
+        ))
     }
 }
 
-struct Strategy(Shape, GameGoal);
+/// Opponent shape and own shape
+struct GamePrediction(Shape, Shape);
 
-impl FromStr for Strategy {
-    type Err = RockPaperScissorsError;
+/// Opponent shape and the desired outcome
+struct Strategy(Shape, GameGoal);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split_once(" ")
-            .ok_or_else(|| RockPaperScissorsError::InvalidStrategyDescriptor(s.to_string()))
-            .and_then(|(opponent, goal)| Ok(Self(opponent.parse()?, goal.parse()?)))
+impl Strategy {
+    fn to_game_prediction(&self, rules: &Rules) -> GamePrediction {
+        GamePrediction(self.0, rules.shape_for_goal(self.0, self.1))
     }
 }
 
-impl Strategy {
-    fn to_game_prediction(&self) -> GamePrediction {
-        GamePrediction(self.0, match self.1 {
-            GameGoal::Lose => self.0.beats(),
-            GameGoal::Draw => self.0,
-            GameGoal::Win => self.0.beaten_by(),
+fn part1<P: AsRef<Path>>(input: P, rules: &Rules) -> Result<usize> {
+    Ok(stream_items_from_file::<P, String>(input)?
+        .map(|l| {
+            let game = rules
+                .parse_prediction(&l.expect("Could not read line"))
+                .expect("Invalid game");
+            rules.score(&game)
         })
-    }
+        .sum())
 }
 
-fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    Ok(stream_items_from_file::<P, GamePrediction>(input)?
-        .map(|g| g.expect("Invalid game").score())
+fn part2<P: AsRef<Path>>(input: P, rules: &Rules) -> Result<usize> {
+    Ok(stream_items_from_file::<P, String>(input)?
+        .map(|l| {
+            let strategy = rules
+                .parse_strategy(&l.expect("Could not read line"))
+                .expect("Invalid strategy");
+            rules.score(&strategy.to_game_prediction(rules))
+        })
         .sum())
 }
 
-fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    Ok(stream_items_from_file::<P, Strategy>(input)?
-       .map(|s| s.expect("Invalid strategy").to_game_prediction().score())
-       .sum())
+/// One way to read the second column of the strategy guide
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Decoding {
+    Shapes(Vec<(String, Shape)>),
+    Goals(Vec<(String, GameGoal)>),
+}
+
+impl Decoding {
+    /// Copy of `rules` that reads the second column according to this decoding
+    fn apply(&self, rules: &Rules) -> Rules {
+        let mut decoded = rules.clone();
+        match self {
+            Decoding::Shapes(mapping) => decoded.own_symbols = mapping.iter().cloned().collect(),
+            Decoding::Goals(mapping) => decoded.goal_symbols = mapping.iter().cloned().collect(),
+        }
+        decoded
+    }
+
+    fn score(&self, rules: &Rules, guide: &[String]) -> Result<usize, RockPaperScissorsError> {
+        let rules = self.apply(rules);
+        guide
+            .iter()
+            .map(|line| match self {
+                Decoding::Shapes(_) => Ok(rules.score(&rules.parse_prediction(line)?)),
+                Decoding::Goals(_) => {
+                    Ok(rules.score(&rules.parse_strategy(line)?.to_game_prediction(&rules)))
+                }
+            })
+            .sum()
+    }
+}
+
+/// A decoding of the guide together with the total score it results in
+struct Candidate<'a> {
+    rules: &'a Rules,
+    decoding: Decoding,
+    score: usize,
+}
+
+impl Display for Candidate<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mapping = match &self.decoding {
+            Decoding::Shapes(mapping) => mapping
+                .iter()
+                .map(|(symbol, shape)| format!("{}={}", symbol, self.rules.shape_names[shape.0]))
+                .join(" "),
+            Decoding::Goals(mapping) => mapping
+                .iter()
+                .map(|(symbol, goal)| format!("{}={:?}", symbol, goal))
+                .join(" "),
+        };
+        write!(f, "{:<40} {:>8}", mapping, self.score)
+    }
+}
+
+/// Score the guide with every assignment of its second column symbols to distinct shapes and
+/// to distinct goals, best candidates first
+fn search_decodings<'a>(guide: &[String], rules: &'a Rules) -> Result<Vec<Candidate<'a>>> {
+    let symbols = guide
+        .iter()
+        .map(|line| {
+            line.split_once(' ')
+                .map(|(_, symbol)| symbol.to_string())
+                .ok_or_else(|| RockPaperScissorsError::InvalidStrategyDescriptor(line.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    if symbols.len() > rules.shape_count() {
+        return Err(RockPaperScissorsError::TooManySymbols {
+            symbols: symbols.len(),
+            shapes: rules.shape_count(),
+        }
+        .into());
+    }
+
+    let shape_decodings = (0..rules.shape_count())
+        .permutations(symbols.len())
+        .map(|shapes| {
+            Decoding::Shapes(
+                symbols
+                    .iter()
+                    .cloned()
+                    .zip(shapes.into_iter().map(Shape))
+                    .collect(),
+            )
+        });
+    // There are only three goals, so guides with more symbols get no goal decodings at all
+    let goal_decodings = [GameGoal::Lose, GameGoal::Draw, GameGoal::Win]
+        .into_iter()
+        .permutations(symbols.len())
+        .map(|goals| Decoding::Goals(symbols.iter().cloned().zip(goals).collect()));
+
+    let mut candidates = shape_decodings
+        .chain(goal_decodings)
+        .map(|decoding| {
+            Ok(Candidate {
+                rules,
+                score: decoding.score(rules, guide)?,
+                decoding,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // Stable sort, so equally scored candidates keep their enumeration order
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
+    Ok(candidates)
 }
 
 fn main() -> Result<()> {
-    println!("Answer for part 1: {}", part1(INPUT)?);
-    println!("Answer for part 2: {}", part2(INPUT)?);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let rules = if args.iter().any(|a| a == "lizard-spock") {
+        Rules::rock_paper_scissors_lizard_spock()
+    } else {
+        Rules::rock_paper_scissors()
+    };
+    if args.iter().any(|a| a == "search") {
+        let guide = stream_items_from_file::<_, String>(INPUT)?.collect::<Result<Vec<_>, _>>()?;
+        for candidate in search_decodings(&guide, &rules)? {
+            println!("{}", candidate);
+        }
+        return Ok(());
+    }
+    println!("Answer for part 1: {}", part1(INPUT, &rules)?);
+    println!("Answer for part 2: {}", part2(INPUT, &rules)?);
 
     Ok(())
 }
@@ -175,8 +408,101 @@ mod tests {
         "},
             None,
         );
-        assert_eq!(part1(&file).unwrap(), 15);
-        assert_eq!(part2(&file).unwrap(), 12);
+        assert_eq!(part1(&file, &Rules::rock_paper_scissors()).unwrap(), 15);
+        assert_eq!(part2(&file, &Rules::rock_paper_scissors()).unwrap(), 12);
+        drop(dir);
+    }
+
+    #[test]
+    fn test_d02_lizard_spock() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let beats = |a: &str, b: &str| {
+            rules.outcome(rules.shape(a), rules.shape(b)) == GameGoal::Win
+                && rules.outcome(rules.shape(b), rules.shape(a)) == GameGoal::Lose
+        };
+        assert!(beats("Scissors", "Paper"));
+        assert!(beats("Paper", "Rock"));
+        assert!(beats("Rock", "Lizard"));
+        assert!(beats("Lizard", "Spock"));
+        assert!(beats("Spock", "Scissors"));
+        assert!(beats("Scissors", "Lizard"));
+        assert!(beats("Lizard", "Paper"));
+        assert!(beats("Paper", "Spock"));
+        assert!(beats("Spock", "Rock"));
+        assert!(beats("Rock", "Scissors"));
+
+        let (dir, file) = create_example_file(
+            indoc! {"
+            E V
+            C Z
+            A Y
+        "},
+            None,
+        );
+        // Rock vs Spock loses, Spock vs Scissors wins, Lizard vs Rock loses
+        assert_eq!(part1(&file, &rules).unwrap(), 1 + 11 + 4);
+        // Scissors wins against Lizard, Scissors draws, Spock loses against Paper
+        let (dir2, file2) = create_example_file("D Z\nC Y\nB X\n", None);
+        assert_eq!(part2(&file2, &rules).unwrap(), 9 + 6 + 5);
+
+        let rules = rules.with_outcome_scores(&[(GameGoal::Lose, 1), (GameGoal::Win, 10)]);
+        assert_eq!(part1(&file, &rules).unwrap(), (1 + 1) + (5 + 10) + (4 + 1));
         drop(dir);
+        drop(dir2);
+    }
+
+    #[test]
+    fn test_d02_decoding_search() {
+        let guide = ["A Y", "B X", "C Z"].map(String::from);
+        let rules = Rules::rock_paper_scissors();
+        let candidates = search_decodings(&guide, &rules).unwrap();
+        assert_eq!(candidates.len(), 12);
+
+        let score_of = |decoding: &Decoding| {
+            candidates
+                .iter()
+                .find(|c| c.decoding == *decoding)
+                .map(|c| c.score)
+        };
+        let puzzle_shapes = Decoding::Shapes(vec![
+            ("X".to_string(), Shape(0)),
+            ("Y".to_string(), Shape(1)),
+            ("Z".to_string(), Shape(2)),
+        ]);
+        let puzzle_goals = Decoding::Goals(vec![
+            ("X".to_string(), GameGoal::Lose),
+            ("Y".to_string(), GameGoal::Draw),
+            ("Z".to_string(), GameGoal::Win),
+        ]);
+        assert_eq!(score_of(&puzzle_shapes), Some(15));
+        assert_eq!(score_of(&puzzle_goals), Some(12));
+        // Winning every round with Paper, Scissors and Rock
+        assert_eq!(candidates[0].score, 2 + 6 + 3 + 6 + 1 + 6);
+        assert!(candidates.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(candidates[0].to_string().ends_with("24"));
+    }
+
+    #[test]
+    fn test_d02_decoding_search_five_symbols() {
+        let guide = ["A V", "B W", "C X", "D Y", "E Z"].map(String::from);
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let candidates = search_decodings(&guide, &rules).unwrap();
+        // Every assignment of five shapes to five symbols, but no goal decodings
+        assert_eq!(candidates.len(), 120);
+        assert!(candidates
+            .iter()
+            .all(|c| matches!(c.decoding, Decoding::Shapes(_))));
+
+        let too_many = ["A U", "B V", "C W", "D X", "E Y", "A Z"].map(String::from);
+        assert!(matches!(
+            search_decodings(&too_many, &rules)
+                .err()
+                .unwrap()
+                .downcast_ref::<RockPaperScissorsError>(),
+            Some(RockPaperScissorsError::TooManySymbols {
+                symbols: 6,
+                shapes: 5
+            })
+        ));
     }
 }
@@ -1,14 +1,12 @@
 use advent_of_code_2022::stream_items_from_file;
 use itertools::Itertools;
-use std::{collections::HashSet, path::Path, str::FromStr};
+use std::{fmt::Display, ops::RangeInclusive, path::Path, str::FromStr};
 use thiserror::Error;
 
 use anyhow::Result;
 
 const INPUT: &str = "input/day03.txt";
-
-// TODO: A more efficient representation would be to convert items to their priority immediately
-// and then use a 64 bit BitSet to represent the Rucksack pockets
+const GROUP_SIZE: usize = 3;
 
 #[derive(Debug, Error)]
 enum RucksackParsingError {
@@ -18,6 +16,16 @@ enum RucksackParsingError {
     InvalidLength(usize),
 }
 
+#[derive(Debug, Error)]
+enum BadgeError {
+    #[error("Group size must be at least 1")]
+    InvalidGroupSize,
+    #[error("Lines {}-{} do not form a complete group", .0.start(), .0.end())]
+    IncompleteGroup(RangeInclusive<usize>),
+    #[error("Invalid group, {0}")]
+    InvalidGroup(GroupBadges),
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct Item(char);
 
@@ -30,6 +38,14 @@ impl Item {
         }
     }
 
+    fn from_priority(priority: usize) -> Option<Item> {
+        match priority {
+            1..=26 => Some(Item((b'a' + priority as u8 - 1) as char)),
+            27..=52 => Some(Item((b'A' + priority as u8 - 27) as char)),
+            _ => None,
+        }
+    }
+
     fn priority(&self) -> usize {
         if self.0.is_ascii_lowercase() {
             self.0 as usize - 'a' as usize + 1
@@ -41,21 +57,74 @@ impl Item {
     }
 }
 
-struct Rucksack(HashSet<Item>, HashSet<Item>);
+/// A set of items stored as a bitmask, bit `n` is set if the item with priority `n` is present
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn insert(&mut self, item: &Item) {
+        self.0 |= 1 << item.priority();
+    }
+
+    fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn items(self) -> impl Iterator<Item = Item> {
+        (0..u64::BITS as usize)
+            .filter(move |p| self.0 & (1 << p) != 0)
+            .filter_map(Item::from_priority)
+    }
+
+    fn priority_sum(self) -> usize {
+        let mut bits = self.0;
+        let mut sum = 0;
+        while bits != 0 {
+            sum += bits.trailing_zeros() as usize;
+            // Clear the lowest set bit
+            bits &= bits - 1;
+        }
+        sum
+    }
+}
+
+impl FromIterator<Item> for ItemSet {
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        let mut set = ItemSet::default();
+        iter.into_iter().for_each(|item| set.insert(&item));
+        set
+    }
+}
+
+struct Rucksack(ItemSet, ItemSet);
+
+impl Rucksack {
+    fn all_items(&self) -> ItemSet {
+        self.0.union(self.1)
+    }
+}
 
 impl FromStr for Rucksack {
     type Err = RucksackParsingError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() % 2 == 0 {
+        if s.len().is_multiple_of(2) {
             let first_pocket = s[0..s.len() / 2]
                 .chars()
-                .map(|c| Item::new(c))
-                .collect::<Result<HashSet<_>, _>>()?;
+                .map(Item::new)
+                .collect::<Result<ItemSet, _>>()?;
             let second_pocket = s[s.len() / 2..]
                 .chars()
-                .map(|c| Item::new(c))
-                .collect::<Result<HashSet<_>, _>>()?;
+                .map(Item::new)
+                .collect::<Result<ItemSet, _>>()?;
 
             Ok(Rucksack(first_pocket, second_pocket))
         } else {
@@ -67,27 +136,99 @@ impl FromStr for Rucksack {
 fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
     Ok(stream_items_from_file::<P, Rucksack>(input)?
         .map(|maybe_rucksack| maybe_rucksack.expect("Invalid Rucksack descriptor"))
-        .map(|r| r.0.intersection(&r.1).map(|i| i.priority()).sum::<usize>())
+        .map(|r| r.0.intersection(r.1).priority_sum())
         .sum())
 }
 
-fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    Ok(stream_items_from_file::<P, Rucksack>(input)?
-        .map(|maybe_rucksack| maybe_rucksack.expect("Invalid Rucksack descriptor"))
-        .tuples()
-        .map(|(r1, r2, r3)| {
-            (&(&r1.0 | &r1.1) & &(&r2.0 | &r2.1))
-                .intersection(&(&r3.0 | &r3.1))
-                .next()
-                .unwrap()
-                .priority()
+/// The items shared by all rucksacks of a group, valid groups share exactly one badge item
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupBadges {
+    /// 1-based input lines of the group's rucksacks
+    lines: RangeInclusive<usize>,
+    shared: ItemSet,
+}
+
+impl GroupBadges {
+    fn is_valid(&self) -> bool {
+        self.shared.len() == 1
+    }
+}
+
+impl Display for GroupBadges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lines {}-{}: ", self.lines.start(), self.lines.end())?;
+        match self.shared.len() {
+            0 => write!(f, "no shared item"),
+            1 => write!(f, "badge {}", self.shared.items().map(|i| i.0).join("")),
+            n => write!(
+                f,
+                "{} shared items ({})",
+                n,
+                self.shared.items().map(|i| i.0).join(", ")
+            ),
+        }
+    }
+}
+
+fn group_badges(rucksacks: &[Rucksack], group_size: usize) -> Result<Vec<GroupBadges>, BadgeError> {
+    if group_size == 0 {
+        return Err(BadgeError::InvalidGroupSize);
+    }
+    let complete = rucksacks.len() - rucksacks.len() % group_size;
+    if complete < rucksacks.len() {
+        return Err(BadgeError::IncompleteGroup(complete + 1..=rucksacks.len()));
+    }
+
+    Ok(rucksacks
+        .chunks(group_size)
+        .enumerate()
+        .map(|(i, group)| GroupBadges {
+            lines: i * group_size + 1..=(i + 1) * group_size,
+            shared: group
+                .iter()
+                .map(Rucksack::all_items)
+                .reduce(ItemSet::intersection)
+                .unwrap_or_default(),
         })
-        .sum())
+        .collect())
+}
+
+fn read_rucksacks<P: AsRef<Path>>(input: P) -> Result<Vec<Rucksack>> {
+    Ok(stream_items_from_file::<P, Rucksack>(input)?.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn part2<P: AsRef<Path>>(input: P, group_size: usize) -> Result<usize> {
+    let groups = group_badges(&read_rucksacks(input)?, group_size)?;
+    if let Some(invalid) = groups.iter().find(|g| !g.is_valid()) {
+        return Err(BadgeError::InvalidGroup(invalid.clone()).into());
+    }
+    Ok(groups.iter().map(|g| g.shared.priority_sum()).sum())
+}
+
+/// All groups that don't share exactly one badge item
+fn badge_report<P: AsRef<Path>>(input: P, group_size: usize) -> Result<Vec<GroupBadges>> {
+    Ok(group_badges(&read_rucksacks(input)?, group_size)?
+        .into_iter()
+        .filter(|g| !g.is_valid())
+        .collect())
 }
 
 fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("badges") {
+        let group_size = match std::env::args().nth(2) {
+            Some(size) => size.parse()?,
+            None => GROUP_SIZE,
+        };
+        let invalid = badge_report(INPUT, group_size)?;
+        println!("{} invalid groups of {}", invalid.len(), group_size);
+        for group in invalid {
+            println!("{}", group);
+        }
+        return Ok(());
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
-    println!("Answer for part 2: {}", part2(INPUT)?);
+    println!("Answer for part 2: {}", part2(INPUT, GROUP_SIZE)?);
 
     Ok(())
 }
@@ -112,7 +253,99 @@ mod tests {
             None,
         );
         assert_eq!(part1(&file).unwrap(), 157);
-        assert_eq!(part2(&file).unwrap(), 70);
+        assert_eq!(part2(&file, GROUP_SIZE).unwrap(), 70);
+        drop(dir);
+    }
+
+    #[test]
+    fn test_d03_item_set() {
+        let rucksack = "vJrwpWtwJgWrhcsFMMfFFhFp".parse::<Rucksack>().unwrap();
+        let shared = rucksack.0.intersection(rucksack.1);
+        assert_eq!(shared, ItemSet(1 << 16));
+        assert_eq!(rucksack.all_items().0.count_ones(), 14);
+        assert_eq!(
+            "aAzZ"
+                .chars()
+                .map(|c| Item::new(c).unwrap())
+                .collect::<ItemSet>()
+                .priority_sum(),
+            1 + 27 + 26 + 52
+        );
+    }
+
+    #[test]
+    fn test_d03_badge_report() {
+        let (dir, file) = create_example_file(
+            indoc! {"
+            vJrwpWtwJgWrhcsFMMfFFhFp
+            jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+            PmmdzqPrVvPwwTWBwg
+            abcd
+            efgh
+            ijkl
+        "},
+            None,
+        );
+        let report = badge_report(&file, GROUP_SIZE).unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].to_string(), "lines 4-6: no shared item");
+        assert!(part2(&file, GROUP_SIZE).is_err());
+
+        let report = badge_report(&file, 2).unwrap();
+        assert_eq!(report.len(), 2);
+        assert_eq!(
+            report[0].to_string(),
+            "lines 1-2: 5 shared items (f, r, s, F, M)"
+        );
+        assert!(badge_report(&file, 4).is_err());
+        assert!(badge_report(&file, 0).is_err());
         drop(dir);
     }
+
+    /// Compare against the previous `HashSet` based implementation on a large generated input.
+    /// Run with `cargo test --release --bin day03 -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_d03_item_sets() {
+        use advent_of_code_2022::test_helpers::TestRng;
+        use std::{collections::HashSet, time::Instant};
+
+        const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let mut rng = TestRng::new(0x2022);
+        let mut next_item = || ITEMS[rng.below(ITEMS.len())] as char;
+        let lines = (0..300_000)
+            .map(|_| (0..48).map(|_| next_item()).collect::<String>())
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let hash_set_sum: usize = lines
+            .iter()
+            .map(|l| {
+                let (a, b) = l.split_at(l.len() / 2);
+                let a = a.chars().map(Item).collect::<HashSet<_>>();
+                let b = b.chars().map(Item).collect::<HashSet<_>>();
+                a.intersection(&b).map(|i| i.priority()).sum::<usize>()
+            })
+            .sum();
+        let hash_set_time = start.elapsed();
+
+        let start = Instant::now();
+        let item_set_sum: usize = lines
+            .iter()
+            .map(|l| {
+                let r = l.parse::<Rucksack>().unwrap();
+                r.0.intersection(r.1).priority_sum()
+            })
+            .sum();
+        let item_set_time = start.elapsed();
+
+        assert_eq!(hash_set_sum, item_set_sum);
+        println!(
+            "{} rucksacks: HashSet {:?}, ItemSet {:?} ({:.1}x)",
+            lines.len(),
+            hash_set_time,
+            item_set_time,
+            hash_set_time.as_secs_f64() / item_set_time.as_secs_f64()
+        );
+    }
 }
@@ -1,7 +1,8 @@
-use advent_of_code_2022::stream_items_from_file;
+use advent_of_code_2022::{interval_set::IntervalSet, stream_items_from_file};
+use itertools::Itertools;
 use std::num::ParseIntError;
 use std::ops::Range;
-use std::{path::Path, str::FromStr};
+use std::{fmt::Display, path::Path, str::FromStr};
 use thiserror::Error;
 
 use anyhow::Result;
@@ -10,7 +11,42 @@ const INPUT: &str = "input/day04.txt";
 
 struct CleaningRangePair(Range<usize>, Range<usize>);
 
+/// How the two assignments of a pair relate to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum RangeRelation {
+    /// No common sections and a gap in between
+    Disjoint,
+    /// No common sections, but one assignment starts right after the other
+    Touching,
+    PartialOverlap,
+    /// One assignment contains all sections of the other and some more
+    Containment,
+    Identical,
+}
+
+impl RangeRelation {
+    const ALL: [RangeRelation; 5] = [
+        RangeRelation::Disjoint,
+        RangeRelation::Touching,
+        RangeRelation::PartialOverlap,
+        RangeRelation::Containment,
+        RangeRelation::Identical,
+    ];
+
+    fn is_full_containment(self) -> bool {
+        matches!(self, RangeRelation::Containment | RangeRelation::Identical)
+    }
+
+    fn has_common_sections(self) -> bool {
+        matches!(
+            self,
+            RangeRelation::PartialOverlap | RangeRelation::Containment | RangeRelation::Identical
+        )
+    }
+}
+
 #[derive(Error, Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 enum CleaningRangeParsingError {
     #[error("Invalid pair '{0}'")]
     InvalidPair(String),
@@ -21,6 +57,22 @@ enum CleaningRangeParsingError {
 }
 
 impl CleaningRangePair {
+    fn classify(&self) -> RangeRelation {
+        let first = IntervalSet::from(self.0.clone());
+        let second = IntervalSet::from(self.1.clone());
+        if self.0 == self.1 {
+            RangeRelation::Identical
+        } else if first.contains_range(&self.1) || second.contains_range(&self.0) {
+            RangeRelation::Containment
+        } else if first.overlaps(&self.1) {
+            RangeRelation::PartialOverlap
+        } else if self.0.end == self.1.start || self.1.end == self.0.start {
+            RangeRelation::Touching
+        } else {
+            RangeRelation::Disjoint
+        }
+    }
+
     fn parse_range(s: &str) -> Result<Range<usize>, CleaningRangeParsingError> {
         let (from, to) = s
             .split_once('-')
@@ -45,36 +97,107 @@ impl FromStr for CleaningRangePair {
     }
 }
 
-trait RangeSubset {
-    fn fully_contains(&self, other: &Self) -> bool;
-    fn overlaps_start(&self, other: &Self) -> bool;
+/// Sections of a whole schedule grouped by how many elves are assigned to them.
+/// Only sections between the first and last assigned section are considered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScheduleCoverage {
+    nobody: IntervalSet<usize>,
+    once: IntervalSet<usize>,
+    many: IntervalSet<usize>,
 }
 
-impl RangeSubset for Range<usize> {
-    fn fully_contains(&self, other: &Self) -> bool {
-        self.start <= other.start && self.end >= other.end
+impl ScheduleCoverage {
+    fn new(assignments: impl IntoIterator<Item = Range<usize>>) -> Self {
+        // Sweep over the start and end points, tracking how many assignments are active
+        let events = assignments
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .flat_map(|r| [(r.start, 1), (r.end, -1)])
+            .sorted()
+            .collect::<Vec<(usize, isize)>>();
+
+        let mut coverage = ScheduleCoverage {
+            nobody: IntervalSet::new(),
+            once: IntervalSet::new(),
+            many: IntervalSet::new(),
+        };
+        let mut active = 0;
+        for (&(start, change), &(end, _)) in events.iter().zip(events.iter().skip(1)) {
+            active += change;
+            match active {
+                0 => coverage.nobody.insert(start..end),
+                1 => coverage.once.insert(start..end),
+                _ => coverage.many.insert(start..end),
+            }
+        }
+        coverage
     }
 
-    fn overlaps_start(&self, other: &Self) -> bool {
-        self.start <= other.start && self.end > other.start
+    fn format_sections(sections: &IntervalSet<usize>) -> String {
+        if sections.is_empty() {
+            return "-".to_string();
+        }
+        sections
+            .iter()
+            .map(|r| format!("{}-{}", r.start, r.end - 1))
+            .join(", ")
     }
 }
 
+impl Display for ScheduleCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Covered by nobody: {}",
+            Self::format_sections(&self.nobody)
+        )?;
+        writeln!(
+            f,
+            "Covered by one elf: {}",
+            Self::format_sections(&self.once)
+        )?;
+        write!(
+            f,
+            "Covered by several elves: {}",
+            Self::format_sections(&self.many)
+        )
+    }
+}
+
+fn read_pairs<P: AsRef<Path>>(input: P) -> Result<Vec<CleaningRangePair>> {
+    Ok(stream_items_from_file::<P, CleaningRangePair>(input)?.collect::<Result<Vec<_>, _>>()?)
+}
+
 fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
     Ok(stream_items_from_file::<P, CleaningRangePair>(input)?
         .map(|p| p.expect("Invalid range descriptor"))
-        .filter(|p| p.0.fully_contains(&p.1) || p.1.fully_contains(&p.0))
+        .filter(|p| p.classify().is_full_containment())
         .count())
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
     Ok(stream_items_from_file::<P, CleaningRangePair>(input)?
         .map(|p| p.expect("Invalid range descriptor"))
-        .filter(|p| p.0.overlaps_start(&p.1) || p.1.overlaps_start(&p.0))
+        .filter(|p| p.classify().has_common_sections())
         .count())
 }
 
 fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("report") {
+        let pairs = read_pairs(INPUT)?;
+        let counts = pairs.iter().map(|p| p.classify()).counts();
+        for overlap in RangeRelation::ALL {
+            println!(
+                "{:?}: {}",
+                overlap,
+                counts.get(&overlap).copied().unwrap_or_default()
+            );
+        }
+        let coverage = ScheduleCoverage::new(pairs.into_iter().flat_map(|p| [p.0, p.1]));
+        println!("{}", coverage);
+        return Ok(());
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2: {}", part2(INPUT)?);
 
@@ -104,4 +227,26 @@ mod tests {
         assert_eq!(part2(&file).unwrap(), 4);
         drop(dir);
     }
+
+    #[test]
+    fn test_d04_classification_and_coverage() {
+        let classify = |s: &str| s.parse::<CleaningRangePair>().unwrap().classify();
+        assert_eq!(classify("2-4,6-8"), RangeRelation::Disjoint);
+        assert_eq!(classify("2-3,4-5"), RangeRelation::Touching);
+        assert_eq!(classify("5-7,7-9"), RangeRelation::PartialOverlap);
+        assert_eq!(classify("6-6,4-6"), RangeRelation::Containment);
+        assert_eq!(classify("3-7,3-7"), RangeRelation::Identical);
+
+        let (dir, file) = create_example_file("2-4,6-8\n3-4,12-14\n", None);
+        let pairs = read_pairs(&file).unwrap();
+        let coverage = ScheduleCoverage::new(pairs.into_iter().flat_map(|p| [p.0, p.1]));
+        assert_eq!(coverage.nobody.ranges(), &[5..6, 9..12]);
+        assert_eq!(coverage.once.ranges(), &[2..3, 6..9, 12..15]);
+        assert_eq!(coverage.many, IntervalSet::from(3..5));
+        assert_eq!(
+            coverage.to_string().lines().next(),
+            Some("Covered by nobody: 5-5, 9-11")
+        );
+        drop(dir);
+    }
 }
@@ -1,114 +1,377 @@
-use std::{fs::File, io::prelude::*, io::BufReader, path::Path};
+use std::{
+    fmt::Display, fs::File, io::prelude::*, io::BufReader, num::NonZeroUsize, ops::Range,
+    path::Path,
+};
+use thiserror::Error;
 
-use anyhow::Result;
-
-// FIXME: This was written in a rush, lots of copy-pasted code between functions, no error handling etc.
+use anyhow::{anyhow, Result};
 
 const INPUT: &str = "input/day05.txt";
 
-struct StacksOfCrates(Vec<Vec<char>>);
+#[derive(Debug, Error)]
+enum CrateInputError {
+    #[error("Missing stack label row")]
+    MissingLabelRow,
+    #[error("Duplicate stack label '{0}'")]
+    DuplicateLabel(String),
+    #[error("Line {line}: malformed crate '{token}'")]
+    InvalidCrate { line: usize, token: String },
+    #[error("Line {line}: crate at column {column} is not below exactly one stack label")]
+    UnalignedCrate { line: usize, column: usize },
+    #[error("Line {line}: crate on stack '{label}' is floating in the air")]
+    FloatingCrate { line: usize, label: String },
+    #[error("Line {line}: invalid instruction '{text}'")]
+    InvalidInstruction { line: usize, text: String },
+    #[error("Line {line}: unknown stack '{label}'")]
+    UnknownStack { line: usize, label: String },
+}
 
-impl StacksOfCrates {
-    fn parse(input: Vec<String>) -> Self {
-        let number_of_stacks = input
-            .iter()
-            .rev()
-            .next()
-            .expect("Can't have empty input")
-            .chars()
-            .filter(|c| *c == '[')
-            .count();
-
-        let mut result = vec![Vec::new(); number_of_stacks];
-
-        for line in input.iter().rev() {
-            line.chars()
-                .enumerate()
-                .filter(|(_, c)| *c == '[')
-                .for_each(|(i, _)| {
-                    let stack_idx = i / 4;
-                    result[stack_idx].push(line.chars().nth(i + 1).expect("Missing char"));
-                });
-        }
+#[derive(Debug, Error)]
+enum RestackingError {
+    #[error("Stack '{label}' holds {available} crates, cannot move {requested}")]
+    NotEnoughCrates {
+        label: String,
+        available: usize,
+        requested: usize,
+    },
+    #[error("Instruction {number}: {source}")]
+    Instruction {
+        /// 1-based position of the instruction in the input
+        number: usize,
+        source: Box<RestackingError>,
+    },
+    #[error("Stack '{0}' is empty")]
+    EmptyStack(String),
+}
 
-        StacksOfCrates(result)
+/// Decides how lifted crates end up on their target stack
+trait Crane {
+    /// Takes the lifted crates from bottom to top and returns them in the order they are placed
+    /// on the target stack
+    fn restack(&self, lifted: Vec<char>) -> Vec<char>;
+}
+
+/// Moves one crate at a time, reversing the order of the moved crates
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn restack(&self, mut lifted: Vec<char>) -> Vec<char> {
+        lifted.reverse();
+        lifted
     }
 }
 
-struct RestackingInstruction(usize, usize, usize);
+/// Moves all crates at once, keeping their order
+struct CrateMover9001;
 
-impl RestackingInstruction {
-    fn parse_from_str(s: &str) -> Self {
-        let parts = s.splitn(6, ' ').collect::<Vec<_>>();
-        RestackingInstruction(
-            parts[1].parse().unwrap(),
-            parts[3].parse().unwrap(),
-            parts[5].parse().unwrap(),
-        )
+impl Crane for CrateMover9001 {
+    fn restack(&self, lifted: Vec<char>) -> Vec<char> {
+        lifted
     }
 }
 
-fn part1<P: AsRef<Path>>(input: P) -> Result<String> {
-    let mut input_lines = BufReader::new(File::open(input)?)
-        .lines()
-        .map(|ml| ml.expect("Could not read"));
-
-    let mut stacks = StacksOfCrates::parse(
-        input_lines
-            .by_ref()
-            .take_while(|l| l.chars().nth(1).unwrap() != '1')
-            .collect(),
-    );
-
-    input_lines.next();
-    for ins in input_lines.map(|l| RestackingInstruction::parse_from_str(&l)) {
-        for _ in 0..ins.0 {
-            let out = stacks.0[ins.1 - 1]
-                .pop()
-                .expect("Could not follow move instruction");
-            stacks.0[ins.2 - 1].push(out);
+/// Moves up to the given number of crates at once, starting from the top
+struct MaxLiftCrane(NonZeroUsize);
+
+impl Crane for MaxLiftCrane {
+    fn restack(&self, lifted: Vec<char>) -> Vec<char> {
+        lifted.rchunks(self.0.get()).flatten().copied().collect()
+    }
+}
+
+/// Whitespace separated tokens of a line together with the columns they occupy
+fn tokens(line: &str) -> Vec<(Range<usize>, &str)> {
+    let mut result = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        if !c.is_whitespace() {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            result.push((s..i, &line[s..i]));
         }
     }
+    result
+}
 
-    Ok(stacks
-        .0
-        .iter()
-        .map(|s| s.last().expect("Could not read top of stack"))
-        .collect::<String>())
+struct StacksOfCrates {
+    labels: Vec<String>,
+    stacks: Vec<Vec<char>>,
 }
 
-fn part2<P: AsRef<Path>>(input: P) -> Result<String> {
-    let mut input_lines = BufReader::new(File::open(input)?)
-        .lines()
-        .map(|ml| ml.expect("Could not read"));
-
-    let mut stacks = StacksOfCrates::parse(
-        input_lines
-            .by_ref()
-            .take_while(|l| l.chars().nth(1).unwrap() != '1')
-            .collect(),
-    );
-
-    input_lines.next();
-    for ins in input_lines.map(|l| RestackingInstruction::parse_from_str(&l)) {
-        let popped = (0..ins.0)
-            .map(|_| {
-                stacks.0[ins.1 - 1]
-                    .pop()
-                    .expect("Could not follow move instruction")
+impl StacksOfCrates {
+    /// Parse the diagram, the last line has to be the row of stack labels.
+    /// Crates are assigned to the stack whose label shares columns with them, so labels can have
+    /// any width as long as they are below their crates.
+    fn parse(input: &[String]) -> Result<Self, CrateInputError> {
+        let (label_row, crate_rows) = input.split_last().ok_or(CrateInputError::MissingLabelRow)?;
+
+        let label_tokens = tokens(label_row);
+        if label_tokens.is_empty() || label_tokens.iter().any(|(_, t)| t.starts_with('[')) {
+            return Err(CrateInputError::MissingLabelRow);
+        }
+        let mut labels = Vec::<String>::with_capacity(label_tokens.len());
+        for (_, label) in &label_tokens {
+            if labels.iter().any(|l| l == label) {
+                return Err(CrateInputError::DuplicateLabel(label.to_string()));
+            }
+            labels.push(label.to_string());
+        }
+
+        let mut stacks = vec![Vec::new(); labels.len()];
+        for (height, (line_idx, line)) in crate_rows.iter().enumerate().rev().enumerate() {
+            let line_number = line_idx + 1;
+            for (columns, token) in tokens(line) {
+                let name = token
+                    .strip_prefix('[')
+                    .and_then(|t| t.strip_suffix(']'))
+                    .filter(|t| t.chars().count() == 1)
+                    .and_then(|t| t.chars().next())
+                    .ok_or_else(|| CrateInputError::InvalidCrate {
+                        line: line_number,
+                        token: token.to_string(),
+                    })?;
+
+                let mut below = label_tokens
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (label_columns, _))| {
+                        label_columns.start < columns.end && columns.start < label_columns.end
+                    })
+                    .map(|(idx, _)| idx);
+                let stack_idx = match (below.next(), below.next()) {
+                    (Some(idx), None) => idx,
+                    _ => {
+                        return Err(CrateInputError::UnalignedCrate {
+                            line: line_number,
+                            column: columns.start + 1,
+                        })
+                    }
+                };
+
+                if stacks[stack_idx].len() != height {
+                    return Err(CrateInputError::FloatingCrate {
+                        line: line_number,
+                        label: labels[stack_idx].clone(),
+                    });
+                }
+                stacks[stack_idx].push(name);
+            }
+        }
+
+        Ok(StacksOfCrates { labels, stacks })
+    }
+
+    fn stack_index(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| l == label)
+    }
+
+    fn apply(
+        &mut self,
+        instruction: &RestackingInstruction,
+        crane: &dyn Crane,
+    ) -> Result<(), RestackingError> {
+        let RestackingInstruction(count, from, to) = *instruction;
+        let available = self.stacks[from].len();
+        if available < count {
+            return Err(RestackingError::NotEnoughCrates {
+                label: self.labels[from].clone(),
+                available,
+                requested: count,
+            });
+        }
+        let lifted = self.stacks[from].split_off(available - count);
+        self.stacks[to].extend(crane.restack(lifted));
+        Ok(())
+    }
+
+    fn apply_all(
+        &mut self,
+        instructions: &[RestackingInstruction],
+        crane: &dyn Crane,
+    ) -> Result<(), RestackingError> {
+        self.trace(instructions, crane, |_, _| {})
+    }
+
+    /// Apply the instructions one by one, `observe` is called with the number of applied
+    /// instructions and the current state before the first and after every instruction
+    fn trace<F>(
+        &mut self,
+        instructions: &[RestackingInstruction],
+        crane: &dyn Crane,
+        mut observe: F,
+    ) -> Result<(), RestackingError>
+    where
+        F: FnMut(usize, &StacksOfCrates),
+    {
+        observe(0, self);
+        for (index, instruction) in instructions.iter().enumerate() {
+            self.apply(instruction, crane)
+                .map_err(|e| RestackingError::Instruction {
+                    number: index + 1,
+                    source: Box::new(e),
+                })?;
+            observe(index + 1, self);
+        }
+        Ok(())
+    }
+
+    fn top_crates(&self) -> Result<String, RestackingError> {
+        self.stacks
+            .iter()
+            .zip(&self.labels)
+            .map(|(s, label)| {
+                s.last()
+                    .copied()
+                    .ok_or_else(|| RestackingError::EmptyStack(label.clone()))
             })
+            .collect()
+    }
+}
+
+impl Display for StacksOfCrates {
+    /// Renders the stacks in the same format as the input diagram
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = self
+            .labels
+            .iter()
+            .map(|l| l.len().max(3))
             .collect::<Vec<_>>();
-        popped.into_iter().rev().for_each(|c| stacks.0[ins.2 - 1].push(c));
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for row in (0..height).rev() {
+            let line = self
+                .stacks
+                .iter()
+                .zip(&widths)
+                .map(|(stack, &width)| match stack.get(row) {
+                    Some(name) => format!("{:<width$}", format!("[{}]", name)),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", line)?;
+        }
+        let labels = self
+            .labels
+            .iter()
+            .zip(&widths)
+            .map(|(label, &width)| format!("{:^width$}", label))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{}", labels)
+    }
+}
+
+/// Number of crates, source stack index and target stack index
+#[derive(Debug, Clone, Copy)]
+struct RestackingInstruction(usize, usize, usize);
+
+impl RestackingInstruction {
+    fn parse(s: &str, line: usize, stacks: &StacksOfCrates) -> Result<Self, CrateInputError> {
+        let invalid = || CrateInputError::InvalidInstruction {
+            line,
+            text: s.to_string(),
+        };
+        let stack = |label: &str| {
+            stacks
+                .stack_index(label)
+                .ok_or_else(|| CrateInputError::UnknownStack {
+                    line,
+                    label: label.to_string(),
+                })
+        };
+
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["move", count, "from", from, "to", to] => Ok(RestackingInstruction(
+                count.parse().map_err(|_| invalid())?,
+                stack(from)?,
+                stack(to)?,
+            )),
+            _ => Err(invalid()),
+        }
     }
+}
 
-    Ok(stacks
-        .0
+/// Read the crate diagram and the instructions, which are separated by an empty line
+fn read_input<P: AsRef<Path>>(input: P) -> Result<(StacksOfCrates, Vec<RestackingInstruction>)> {
+    let lines = BufReader::new(File::open(input)?)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    let diagram_end = lines
         .iter()
-        .map(|s| s.last().expect("Could not read top of stack"))
-        .collect::<String>())
+        .position(|l| l.trim().is_empty())
+        .unwrap_or(lines.len());
+
+    let stacks = StacksOfCrates::parse(&lines[..diagram_end])?;
+    let instructions = lines
+        .iter()
+        .enumerate()
+        .skip(diagram_end + 1)
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(idx, l)| RestackingInstruction::parse(l, idx + 1, &stacks))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((stacks, instructions))
+}
+
+fn top_crates_after_restacking<P: AsRef<Path>>(input: P, crane: &dyn Crane) -> Result<String> {
+    let (mut stacks, instructions) = read_input(input)?;
+    stacks.apply_all(&instructions, crane)?;
+    Ok(stacks.top_crates()?)
+}
+
+fn part1<P: AsRef<Path>>(input: P) -> Result<String> {
+    top_crates_after_restacking(input, &CrateMover9000)
+}
+
+fn part2<P: AsRef<Path>>(input: P) -> Result<String> {
+    top_crates_after_restacking(input, &CrateMover9001)
 }
 
 fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let crane: &dyn Crane = if args.iter().any(|a| a == "9001") {
+        &CrateMover9001
+    } else {
+        &CrateMover9000
+    };
+    match args.first().map(String::as_str) {
+        Some("max-lift") => {
+            let lift = args
+                .get(1)
+                .ok_or_else(|| anyhow!("Missing maximum lift"))?
+                .parse::<usize>()?;
+            let lift = NonZeroUsize::new(lift)
+                .ok_or_else(|| anyhow!("A crane needs to lift at least one crate"))?;
+            println!(
+                "Top crates with a maximum lift of {}: {}",
+                lift,
+                top_crates_after_restacking(INPUT, &MaxLiftCrane(lift))?
+            );
+            return Ok(());
+        }
+        Some("render") => {
+            let step = args
+                .get(1)
+                .ok_or_else(|| anyhow!("Missing step"))?
+                .parse::<usize>()?;
+            let (mut stacks, instructions) = read_input(INPUT)?;
+            if step > instructions.len() {
+                return Err(anyhow!("There are only {} steps", instructions.len()));
+            }
+            stacks.apply_all(&instructions[..step], crane)?;
+            println!("{}", stacks);
+            return Ok(());
+        }
+        Some("trace") => {
+            let (mut stacks, instructions) = read_input(INPUT)?;
+            stacks.trace(&instructions, crane, |step, stacks| {
+                println!("After step {}:\n{}\n", step, stacks)
+            })?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2: {}", part2(INPUT)?);
 
@@ -141,4 +404,135 @@ mod tests {
         assert_eq!(part2(&file).unwrap(), "MCD");
         drop(dir);
     }
+
+    #[test]
+    fn test_d05_diagram_parsing() {
+        let parse = |diagram: &str| {
+            StacksOfCrates::parse(&diagram.lines().map(String::from).collect::<Vec<_>>())
+        };
+
+        let stacks = parse(indoc! {"
+                                        [K]
+            [A]                     [J] [L]
+            [B] [C] [D] [E] [F] [G] [H] [I]
+             1   2   3   4   5   6   7  10
+        "})
+        .unwrap();
+        assert_eq!(stacks.labels.last().unwrap(), "10");
+        assert_eq!(stacks.stacks[7], vec!['I', 'L', 'K']);
+        assert_eq!(stacks.top_crates().unwrap(), "ACDEFGJK");
+
+        let stacks = parse("[A] [B]\nfoo bar").unwrap();
+        assert_eq!(stacks.stack_index("bar"), Some(1));
+
+        assert!(matches!(
+            parse("[A]\n[B] [C]\n 1"),
+            Err(CrateInputError::UnalignedCrate { line: 2, column: 5 })
+        ));
+        assert!(matches!(
+            parse("    [A]\n[B]    \n 1   2"),
+            Err(CrateInputError::FloatingCrate { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("[AB]\n 1"),
+            Err(CrateInputError::InvalidCrate { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("[A]\n[B]"),
+            Err(CrateInputError::MissingLabelRow)
+        ));
+        assert!(matches!(
+            parse("[A] [B]\n 1   1"),
+            Err(CrateInputError::DuplicateLabel(_))
+        ));
+        assert!(matches!(parse(""), Err(CrateInputError::MissingLabelRow)));
+
+        let stacks = parse("[A]\n 1").unwrap();
+        assert!(matches!(
+            RestackingInstruction::parse("move 1 from 1 to 2", 3, &stacks),
+            Err(CrateInputError::UnknownStack { line: 3, .. })
+        ));
+        assert!(matches!(
+            RestackingInstruction::parse("move x from 1 to 1", 3, &stacks),
+            Err(CrateInputError::InvalidInstruction { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_d05_cranes() {
+        let diagram = ["[A]    ", "[B]    ", "[C]    ", "[D] [E]", " 1   2 "].map(String::from);
+        let move_all = RestackingInstruction(4, 0, 1);
+        let restacked = |crane: &dyn Crane| {
+            let mut stacks = StacksOfCrates::parse(&diagram).unwrap();
+            stacks.apply(&move_all, crane).unwrap();
+            stacks.stacks[1].iter().collect::<String>()
+        };
+        assert_eq!(restacked(&CrateMover9000), "EABCD");
+        assert_eq!(restacked(&CrateMover9001), "EDCBA");
+        let max_lift = |n| MaxLiftCrane(NonZeroUsize::new(n).unwrap());
+        assert_eq!(restacked(&max_lift(1)), "EABCD");
+        assert_eq!(restacked(&max_lift(3)), "ECBAD");
+        assert_eq!(restacked(&max_lift(4)), "EDCBA");
+
+        let mut stacks = StacksOfCrates::parse(&diagram).unwrap();
+        let instructions = [
+            RestackingInstruction(1, 1, 0),
+            RestackingInstruction(2, 1, 0),
+        ];
+        let err = stacks
+            .apply_all(&instructions, &CrateMover9000)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RestackingError::Instruction { number: 2, .. }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Instruction 2: Stack '2' holds 0 crates, cannot move 2"
+        );
+        assert!(matches!(
+            stacks.top_crates(),
+            Err(RestackingError::EmptyStack(label)) if label == "2"
+        ));
+    }
+
+    #[test]
+    fn test_d05_rendering() {
+        let diagram =
+            ["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", " 1   2   3 "].map(String::from);
+        let mut stacks = StacksOfCrates::parse(&diagram).unwrap();
+        assert_eq!(stacks.to_string(), diagram.join("\n"));
+
+        let mut frames = Vec::new();
+        stacks
+            .trace(
+                &[
+                    RestackingInstruction(1, 1, 0),
+                    RestackingInstruction(3, 0, 2),
+                ],
+                &CrateMover9000,
+                |step, stacks| frames.push((step, stacks.to_string())),
+            )
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].1, diagram.join("\n"));
+        let expected = [
+            "        [Z]",
+            "        [N]",
+            "    [C] [D]",
+            "    [M] [P]",
+            " 1   2   3 ",
+        ];
+        assert_eq!(frames[2], (2, expected.join("\n")));
+
+        // Wide labels are rendered so that the output can be parsed again
+        let wide = ["[A]       [B]", "one two three"].map(String::from);
+        let rendered = StacksOfCrates::parse(&wide).unwrap().to_string();
+        assert_eq!(rendered, "[A]     [B]  \none two three");
+        let reparsed = rendered.lines().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            StacksOfCrates::parse(&reparsed).unwrap().stacks[2],
+            vec!['B']
+        );
+    }
 }
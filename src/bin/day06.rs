@@ -1,53 +1,214 @@
+use advent_of_code_2022::distinct_window::SlidingDistinct;
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::VecDeque,
     fs::File,
-    io::prelude::*,
-    io::BufReader,
+    io::{self, prelude::*, BufReader},
     path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 const INPUT: &str = "input/day06.txt";
 
-fn find_start_of_entity_marker(
-    marker_len: usize,
-    mut input: impl Iterator<Item = u8>,
-) -> Option<(usize, impl Iterator<Item = u8>)> {
-    let mut last_n_chars = VecDeque::new();
-    input
-        .by_ref()
-        .take_while(|c| {
-            if last_n_chars.len() >= marker_len {
-                last_n_chars.pop_front();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    const ALL: [MarkerKind; 2] = [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage];
+
+    fn marker_len(self) -> usize {
+        match self {
+            MarkerKind::StartOfPacket => 4,
+            MarkerKind::StartOfMessage => 14,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Marker {
+    kind: MarkerKind,
+    /// Offset of the first byte after the marker
+    end: usize,
+}
+
+impl Marker {
+    fn start(&self) -> usize {
+        self.end - self.kind.marker_len()
+    }
+}
+
+/// Looks for markers of all kinds in a stream, one byte at a time.
+/// Markers of the same kind never overlap, the search for the next one starts right after the
+/// previous marker.
+struct MarkerScanner {
+    windows: [SlidingDistinct; 2],
+    offset: usize,
+}
+
+impl MarkerScanner {
+    fn new() -> Self {
+        MarkerScanner {
+            windows: MarkerKind::ALL.map(|kind| SlidingDistinct::new(kind.marker_len())),
+            offset: 0,
+        }
+    }
+
+    /// The markers that end with `byte`
+    fn push(&mut self, byte: u8) -> impl Iterator<Item = Marker> {
+        self.offset += 1;
+        let mut found = [None; 2];
+        for (idx, kind) in MarkerKind::ALL.into_iter().enumerate() {
+            let window = self.windows[idx].push(byte);
+            if window.len == kind.marker_len() && window.is_all_distinct() {
+                self.windows[idx].clear();
+                found[idx] = Some(Marker {
+                    kind,
+                    end: self.offset,
+                });
+            }
+        }
+        found.into_iter().flatten()
+    }
+}
+
+/// Yields every marker of a stream
+struct MarkerDecoder<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    scanner: MarkerScanner,
+    pending: VecDeque<Marker>,
+}
+
+impl<R: Read> MarkerDecoder<R> {
+    fn new(reader: R) -> Self {
+        MarkerDecoder {
+            bytes: BufReader::new(reader).bytes(),
+            scanner: MarkerScanner::new(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for MarkerDecoder<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            match self.bytes.next()? {
+                Ok(byte) => self.pending.extend(self.scanner.push(byte)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// The bytes between one start-of-message marker and the next one, or the end of the stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Message {
+    /// Offset of the first payload byte
+    start: usize,
+    payload: Vec<u8>,
+}
+
+/// Splits a stream into messages, everything before the first start-of-message marker is dropped
+struct MessageFramer<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    scanner: MarkerScanner,
+    current: Option<Message>,
+}
+
+impl<R: Read> MessageFramer<R> {
+    fn new(reader: R) -> Self {
+        MessageFramer {
+            bytes: BufReader::new(reader).bytes(),
+            scanner: MarkerScanner::new(),
+            current: None,
+        }
+    }
+}
+
+impl<R: Read> Iterator for MessageFramer<R> {
+    type Item = io::Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let byte = match self.bytes.next() {
+                Some(Ok(byte)) => byte,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.current.take().map(Ok),
+            };
+            if let Some(message) = self.current.as_mut() {
+                message.payload.push(byte);
+            }
+            if let Some(marker) = self
+                .scanner
+                .push(byte)
+                .find(|m| m.kind == MarkerKind::StartOfMessage)
+            {
+                let next = Message {
+                    start: marker.end,
+                    payload: Vec::new(),
+                };
+                if let Some(mut finished) = self.current.replace(next) {
+                    // The marker itself belongs to neither message
+                    let payload_len = marker.start() - finished.start;
+                    finished.payload.truncate(payload_len);
+                    return Some(Ok(finished));
+                }
             }
-            last_n_chars.push_back(*c);
-            // TODO: Use a bitset here
-            last_n_chars.iter().collect::<HashSet<_>>().len() != marker_len
-        })
-        .enumerate()
-        .last()
-        // Adjust for 1-offset and the one skipped byte from take_while
-        .map(|(idx, _)| (idx + 2, input))
+        }
+    }
 }
 
-fn run<P: AsRef<Path>>(marker_len: usize, input: P) -> Result<usize> {
-    let file = BufReader::new(File::open(input)?);
-    let input_bytes = file.bytes().map(|mb| mb.expect("Reading failed"));
-    let (idx, _) =
-        find_start_of_entity_marker(marker_len, input_bytes).expect("Found no SOP marker");
-    Ok(idx)
+fn run<P: AsRef<Path>>(kind: MarkerKind, input: P) -> Result<usize> {
+    for marker in MarkerDecoder::new(File::open(input)?) {
+        let marker = marker?;
+        if marker.kind == kind {
+            return Ok(marker.end);
+        }
+    }
+    Err(anyhow!("Found no {:?} marker", kind))
 }
 
 fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    run(4, input)
+    run(MarkerKind::StartOfPacket, input)
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    run(14, input)
+    run(MarkerKind::StartOfMessage, input)
 }
 
 fn main() -> Result<()> {
+    let mode = std::env::args().nth(1);
+    if matches!(mode.as_deref(), Some("markers") | Some("frames")) {
+        // Read from stdin when the input is given as `-`
+        let reader: Box<dyn Read> = match std::env::args().nth(2).as_deref() {
+            Some("-") => Box::new(io::stdin()),
+            Some(path) => Box::new(File::open(path)?),
+            None => Box::new(File::open(INPUT)?),
+        };
+        if mode.as_deref() == Some("markers") {
+            for marker in MarkerDecoder::new(reader) {
+                let marker = marker?;
+                println!("{:?} at {}..{}", marker.kind, marker.start(), marker.end);
+            }
+        } else {
+            for message in MessageFramer::new(reader) {
+                let message = message?;
+                println!(
+                    "Message at {} ({} bytes): {}",
+                    message.start,
+                    message.payload.len(),
+                    String::from_utf8_lossy(&message.payload).trim_end()
+                );
+            }
+        }
+        return Ok(());
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2: {}", part2(INPUT)?);
 
@@ -66,4 +227,42 @@ mod tests {
         assert_eq!(part2(&file).unwrap(), 19);
         drop(dir);
     }
+
+    #[test]
+    fn test_d06_framing() {
+        let stream = b"aaabcdbbbbbbbbbbbbbbbabcdefghijklmnHELLOaaabcdefghijklmnWORLD";
+        let markers = MarkerDecoder::new(&stream[..])
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        let packets = markers
+            .iter()
+            .filter(|m| m.kind == MarkerKind::StartOfPacket)
+            .map(|m| m.end)
+            .collect::<Vec<_>>();
+        assert_eq!(packets, vec![6, 25, 29, 33, 37, 46, 50, 54, 58]);
+        let messages = markers
+            .iter()
+            .filter(|m| m.kind == MarkerKind::StartOfMessage)
+            .map(|m| m.start()..m.end)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec![21..35, 42..56]);
+
+        let frames = MessageFramer::new(&stream[..])
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                Message {
+                    start: 35,
+                    payload: b"HELLOaa".to_vec()
+                },
+                Message {
+                    start: 56,
+                    payload: b"WORLD".to_vec()
+                }
+            ]
+        );
+        assert_eq!(MessageFramer::new(&b"abcabc"[..]).count(), 0);
+    }
 }
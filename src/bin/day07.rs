@@ -1,10 +1,23 @@
-use advent_of_code_2022::stream_items_from_file;
-use std::{collections::HashMap, num::ParseIntError, path::Path, str::FromStr};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::Display,
+    fs::File,
+    io::{prelude::*, BufReader},
+    num::ParseIntError,
+    path::Path,
+    str::FromStr,
+};
 use thiserror::Error;
 
-use anyhow::Result;
+use advent_of_code_2022::top_k::top_k_by_key;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
 
 const INPUT: &str = "input/day07.txt";
+const DISK_SIZE: usize = 70000000;
+const REQUIRED_FREE: usize = 30000000;
+/// Number of size buckets `DiskPlanner::minimal_set` works with
+const PLANNER_BUCKETS: usize = 1024;
 
 #[derive(Debug, Clone)]
 enum Command {
@@ -24,14 +37,11 @@ impl FromStr for Command {
     type Err = CommandParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("ls") {
-            Ok(Command::Ls)
-        } else if s.starts_with("cd") {
-            s.split_once(" ")
-                .ok_or_else(|| CommandParseError::MissingArgument(s.to_string()))
-                .map(|(_, arg)| Command::Cd(arg.to_string()))
-        } else {
-            Err(CommandParseError::UnknownCommand(s.to_string()))
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["ls"] => Ok(Command::Ls),
+            ["cd", path] => Ok(Command::Cd(path.to_string())),
+            ["cd"] => Err(CommandParseError::MissingArgument(s.to_string())),
+            _ => Err(CommandParseError::UnknownCommand(s.to_string())),
         }
     }
 }
@@ -81,31 +91,63 @@ enum InputParseError {
     ListingParseError(#[from] ListingEntryParseError),
 }
 
+#[derive(Debug, Error)]
+enum TranscriptError {
+    #[error("Line {line}: {source}")]
+    Parse {
+        line: usize,
+        source: InputParseError,
+    },
+    #[error("Line {line}: cannot navigate above the root directory")]
+    AboveRoot { line: usize },
+    #[error("Line {line}: '{name}' is not a directory")]
+    NotADirectory { line: usize, name: String },
+    #[error("Line {line}: directory '{name}' does not exist")]
+    NoSuchDirectory { line: usize, name: String },
+    #[error("Line {line}: listing output without a preceding ls")]
+    UnexpectedListing { line: usize },
+    #[error("Line {line}: listing of /{path} contradicts what is known ({reason})")]
+    ContradictingListing {
+        line: usize,
+        path: String,
+        reason: String,
+    },
+}
+
 impl FromStr for CommandOrListing {
     type Err = InputParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with('$') {
-            Ok(CommandOrListing::Command(s[2..].parse()?))
+        if let Some(command) = s.strip_prefix('$') {
+            Ok(CommandOrListing::Command(command.parse()?))
         } else {
             Ok(CommandOrListing::Listing(s.parse()?))
         }
     }
 }
 
+#[derive(Debug, Clone, Default)]
+struct Directory {
+    children: HashMap<String, FileSystemNode>,
+    /// Whether the contents are known from a listing, otherwise the directory has only been
+    /// entered and its children are the ones that were entered as well
+    listed: bool,
+}
+
 #[derive(Debug, Clone)]
 enum FileSystemNode {
-    Directory(HashMap<String, FileSystemNode>),
+    Directory(Directory),
     File(usize),
 }
 
 impl FileSystemNode {
     fn resolve_mut(&mut self, path: &[String]) -> Option<&mut FileSystemNode> {
-        if path.len() == 0 {
+        if path.is_empty() {
             Some(self)
         } else {
             match self {
-                FileSystemNode::Directory(children) => children
+                FileSystemNode::Directory(dir) => dir
+                    .children
                     .get_mut(&path[0])
                     .and_then(|child| child.resolve_mut(&path[1..])),
                 FileSystemNode::File(_) => None,
@@ -113,131 +155,651 @@ impl FileSystemNode {
         }
     }
 
-    fn add_child_directory(&mut self, name: String) {
+    fn is_dir(&self) -> bool {
         match self {
-            FileSystemNode::Directory(children) => {
-                children.insert(name, Self::Directory(Default::default()))
-            }
-            FileSystemNode::File(_) => panic!("Can't add children to a file"),
-        };
+            FileSystemNode::Directory(_) => true,
+            FileSystemNode::File(_) => false,
+        }
     }
 
-    fn add_file(&mut self, name: String, size: usize) {
-        match self {
-            FileSystemNode::Directory(children) => children.insert(name, Self::File(size)),
-            FileSystemNode::File(_) => panic!("Can't add children to a file"),
-        };
+    /// Whether the node matches what a listing entry says about it
+    fn matches(&self, entry: &ListingEntry) -> bool {
+        match (self, entry) {
+            (FileSystemNode::Directory(_), ListingEntry::Directory(_)) => true,
+            (FileSystemNode::File(size), ListingEntry::File(listed, _)) => size == listed,
+            _ => false,
+        }
     }
+}
 
-    fn get_size(&self) -> usize {
+impl ListingEntry {
+    fn name(&self) -> &str {
         match self {
-            FileSystemNode::Directory(children) => children.values().map(|c| c.get_size()).sum(),
-            FileSystemNode::File(size) => *size,
+            ListingEntry::Directory(name) | ListingEntry::File(_, name) => name,
         }
     }
+}
 
-    // TODO: This could be easily improved by also taking a fold function instead of the
-    // vec collection
-    fn find_elements<'a, F>(&'a self, pred: &F, target: &mut Vec<&'a Self>)
-    where
-        F: Fn(&Self) -> bool,
-    {
-        if pred(self) {
-            target.push(self)
+/// Replays a shell transcript to reconstruct the file system it explored
+struct TranscriptInterpreter {
+    root: FileSystemNode,
+    current_directory: Vec<String>,
+    /// Line of the `ls` whose output is currently read and the entries seen so far
+    listing: Option<(usize, Vec<ListingEntry>)>,
+}
+
+impl TranscriptInterpreter {
+    fn new() -> Self {
+        TranscriptInterpreter {
+            root: FileSystemNode::Directory(Directory::default()),
+            current_directory: Vec::new(),
+            listing: None,
         }
-        match self {
-            FileSystemNode::Directory(children) => {
-                children
-                    .values()
-                    .for_each(|c| c.find_elements(pred, target));
+    }
+
+    fn current_dir_mut(&mut self) -> &mut Directory {
+        match self.root.resolve_mut(&self.current_directory) {
+            Some(FileSystemNode::Directory(dir)) => dir,
+            _ => unreachable!("The current directory always exists"),
+        }
+    }
+
+    fn observe(&mut self, line: usize, input: &str) -> Result<(), TranscriptError> {
+        let parsed = input
+            .parse::<CommandOrListing>()
+            .map_err(|source| TranscriptError::Parse { line, source })?;
+        match parsed {
+            CommandOrListing::Command(command) => {
+                self.finish_listing()?;
+                match command {
+                    Command::Cd(path) => self.change_directory(line, &path)?,
+                    Command::Ls => self.listing = Some((line, Vec::new())),
+                }
             }
-            FileSystemNode::File(_) => (),
+            CommandOrListing::Listing(entry) => match self.listing.as_mut() {
+                Some((_, entries)) => entries.push(entry),
+                None => return Err(TranscriptError::UnexpectedListing { line }),
+            },
         }
+        Ok(())
     }
 
-    fn is_dir(&self) -> bool {
-        match self {
-            FileSystemNode::Directory(_) => true,
-            FileSystemNode::File(_) => false,
+    /// Follow an absolute or relative path one segment at a time
+    fn change_directory(&mut self, line: usize, path: &str) -> Result<(), TranscriptError> {
+        if path.starts_with('/') {
+            self.current_directory.clear();
+        }
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            if segment == ".." {
+                self.current_directory
+                    .pop()
+                    .ok_or(TranscriptError::AboveRoot { line })?;
+                continue;
+            }
+
+            let current = self.current_dir_mut();
+            let listed = current.listed;
+            match current.children.entry(segment.to_string()) {
+                Entry::Occupied(child) if !child.get().is_dir() => {
+                    return Err(TranscriptError::NotADirectory {
+                        line,
+                        name: segment.to_string(),
+                    })
+                }
+                Entry::Occupied(_) => (),
+                Entry::Vacant(_) if listed => {
+                    return Err(TranscriptError::NoSuchDirectory {
+                        line,
+                        name: segment.to_string(),
+                    })
+                }
+                Entry::Vacant(child) => {
+                    child.insert(FileSystemNode::Directory(Directory::default()));
+                }
+            }
+            self.current_directory.push(segment.to_string());
         }
+        Ok(())
     }
-}
 
-fn observe_commands(input: impl Iterator<Item = CommandOrListing>) -> FileSystemNode {
-    let mut current_directory = Vec::<String>::new();
-    let mut filesystem_root = FileSystemNode::Directory(Default::default());
+    /// Merge the output of the last `ls` into the tree. A directory can be listed several times,
+    /// but every listing has to agree with everything known about it.
+    fn finish_listing(&mut self) -> Result<(), TranscriptError> {
+        let Some((line, entries)) = self.listing.take() else {
+            return Ok(());
+        };
+        let path = self.current_directory.join("/");
+        let contradiction = |reason: String| TranscriptError::ContradictingListing {
+            line,
+            path: path.clone(),
+            reason,
+        };
 
-    for command_or_listing in input {
-        match command_or_listing {
-            CommandOrListing::Command(command) => match command {
-                Command::Cd(path) => {
-                    if path == "/" {
-                        current_directory.clear();
-                    } else if path == ".." {
-                        current_directory
-                            .pop()
-                            .expect("Can not navigate above root");
-                    } else {
-                        current_directory.push(path);
-                    }
+        let dir = self.current_dir_mut();
+        let mut listed = HashSet::new();
+        for entry in &entries {
+            if !listed.insert(entry.name()) {
+                return Err(contradiction(format!("'{}' is listed twice", entry.name())));
+            }
+        }
+        if let Some(missing) = dir.children.keys().find(|n| !listed.contains(n.as_str())) {
+            return Err(contradiction(format!("'{}' is missing", missing)));
+        }
+        for entry in entries {
+            match dir.children.get(entry.name()) {
+                Some(existing) if !existing.matches(&entry) => {
+                    return Err(contradiction(format!("'{}' has changed", entry.name())))
                 }
-                Command::Ls => (),
+                Some(_) => (),
+                None if dir.listed => {
+                    return Err(contradiction(format!("'{}' is new", entry.name())))
+                }
+                None => {
+                    let node = match &entry {
+                        ListingEntry::Directory(_) => {
+                            FileSystemNode::Directory(Directory::default())
+                        }
+                        ListingEntry::File(size, _) => FileSystemNode::File(*size),
+                    };
+                    dir.children.insert(entry.name().to_string(), node);
+                }
+            }
+        }
+        dir.listed = true;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<FileSystemNode, TranscriptError> {
+        self.finish_listing()?;
+        Ok(self.root)
+    }
+}
+
+fn observe_commands<'a>(
+    input: impl Iterator<Item = &'a str>,
+) -> Result<FileSystemNode, TranscriptError> {
+    let mut interpreter = TranscriptInterpreter::new();
+    for (idx, line) in input.enumerate() {
+        interpreter.observe(idx + 1, line)?;
+    }
+    interpreter.finish()
+}
+
+/// A node of the reconstructed tree with the size of its whole subtree
+#[derive(Debug, Clone)]
+struct TreeNode {
+    path: String,
+    name: String,
+    depth: usize,
+    size: usize,
+    is_dir: bool,
+    /// Indices of the children, sorted by name
+    children: Vec<usize>,
+    /// Index right after the last node of this subtree
+    subtree_end: usize,
+}
+
+/// Flattened file system where every directory size is computed once on construction.
+/// Nodes are stored in pre-order, the root is at index 0.
+struct FileSystemTree {
+    nodes: Vec<TreeNode>,
+}
+
+impl FileSystemTree {
+    fn new(root: &FileSystemNode) -> Self {
+        let mut tree = FileSystemTree { nodes: Vec::new() };
+        tree.add(root, "/", String::new(), 0);
+        tree
+    }
+
+    fn add(&mut self, node: &FileSystemNode, name: &str, path: String, depth: usize) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(TreeNode {
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.clone()
             },
-            CommandOrListing::Listing(listing_entry) => {
-                let current_dir = filesystem_root
-                    .resolve_mut(&current_directory)
-                    .expect("Current path does not exist");
-                match listing_entry {
-                    ListingEntry::Directory(name) => current_dir.add_child_directory(name),
-                    ListingEntry::File(name, size) => current_dir.add_file(size, name),
+            name: name.to_string(),
+            depth,
+            size: 0,
+            is_dir: node.is_dir(),
+            children: Vec::new(),
+            subtree_end: idx + 1,
+        });
+        match node {
+            FileSystemNode::Directory(dir) => {
+                let mut names = dir.children.keys().collect::<Vec<_>>();
+                names.sort();
+                for child_name in names {
+                    let child = self.add(
+                        &dir.children[child_name],
+                        child_name,
+                        format!("{}/{}", path, child_name),
+                        depth + 1,
+                    );
+                    self.nodes[idx].size += self.nodes[child].size;
+                    self.nodes[idx].children.push(child);
                 }
             }
+            FileSystemNode::File(size) => self.nodes[idx].size = *size,
         }
+        self.nodes[idx].subtree_end = self.nodes.len();
+        idx
+    }
+
+    /// Whether one of the two nodes is part of the other one's subtree
+    fn nested(&self, a: usize, b: usize) -> bool {
+        let (outer, inner) = if a <= b { (a, b) } else { (b, a) };
+        inner < self.nodes[outer].subtree_end
+    }
+
+    fn root(&self) -> &TreeNode {
+        &self.nodes[0]
+    }
+
+    fn directories(&self) -> impl Iterator<Item = &TreeNode> {
+        self.nodes.iter().filter(|n| n.is_dir)
+    }
+
+    fn files(&self) -> impl Iterator<Item = &TreeNode> {
+        self.nodes.iter().filter(|n| !n.is_dir)
+    }
+
+    fn directories_below(&self, size: usize) -> impl Iterator<Item = &TreeNode> {
+        self.directories().filter(move |d| d.size < size)
+    }
+
+    fn directories_above(&self, size: usize) -> impl Iterator<Item = &TreeNode> {
+        self.directories().filter(move |d| d.size > size)
     }
 
-    filesystem_root
+    fn largest_files(&self, k: usize) -> Vec<&TreeNode> {
+        top_k_by_key(self.files(), k, |f| f.size)
+    }
+
+    /// The smallest directory whose deletion frees at least `size` bytes
+    fn smallest_directory_freeing(&self, size: usize) -> Option<&TreeNode> {
+        self.directories()
+            .filter(|d| d.size >= size)
+            .min_by_key(|d| d.size)
+    }
+
+    /// Directory sizes like `du`, every directory after its subdirectories
+    fn du(&self) -> String {
+        let mut lines = Vec::new();
+        self.du_lines(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn du_lines(&self, idx: usize, lines: &mut Vec<String>) {
+        let node = &self.nodes[idx];
+        for &child in node.children.iter().filter(|&&c| self.nodes[c].is_dir) {
+            self.du_lines(child, lines);
+        }
+        lines.push(format!("{}\t{}", node.size, node.path));
+    }
+
+    /// Indented listing of all nodes in the format of the puzzle description
+    fn tree(&self) -> String {
+        self.nodes
+            .iter()
+            .map(|n| {
+                let details = if n.is_dir {
+                    format!("dir, size={}", n.size)
+                } else {
+                    format!("file, size={}", n.size)
+                };
+                format!("{}- {} ({})", "  ".repeat(n.depth), n.name, details)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn read_file_system<P: AsRef<Path>>(input: P) -> Result<FileSystemNode> {
+    let lines = BufReader::new(File::open(input)?)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(observe_commands(lines.iter().map(String::as_str))?)
 }
 
 fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let fs_state = observe_commands(
-        stream_items_from_file::<P, CommandOrListing>(input)?
-            .map(|r| r.expect("Invalid line in input")),
-    );
+    let tree = FileSystemTree::new(&read_file_system(input)?);
+    Ok(tree.directories_below(100000).map(|d| d.size).sum())
+}
 
-    let mut large_dirs = Vec::new();
-    fs_state.find_elements(&|e| e.is_dir() && e.get_size() < 100000, &mut large_dirs);
+#[derive(Debug, Error)]
+enum PlanningError {
+    #[error("{used} bytes are used, but the disk only holds {disk_size}")]
+    UsedExceedsDisk { used: usize, disk_size: usize },
+}
+
+#[derive(Debug)]
+enum CleanupPlan<'a> {
+    NothingToDelete {
+        free: usize,
+    },
+    Delete {
+        need_to_free: usize,
+        directories: Vec<&'a TreeNode>,
+    },
+    /// Even deleting everything would not free enough space
+    Impossible {
+        need_to_free: usize,
+    },
+}
+
+impl CleanupPlan<'_> {
+    fn freed(&self) -> usize {
+        match self {
+            CleanupPlan::Delete { directories, .. } => directories.iter().map(|d| d.size).sum(),
+            _ => 0,
+        }
+    }
+}
+
+impl Display for CleanupPlan<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CleanupPlan::NothingToDelete { free } => {
+                write!(f, "Nothing to delete, {} bytes are already free", free)
+            }
+            CleanupPlan::Delete {
+                need_to_free,
+                directories,
+            } => {
+                write!(
+                    f,
+                    "Delete {} to free {} of {} bytes",
+                    directories.iter().map(|d| d.path.as_str()).join(", "),
+                    self.freed(),
+                    need_to_free
+                )
+            }
+            CleanupPlan::Impossible { need_to_free } => {
+                write!(f, "Cannot free {} bytes", need_to_free)
+            }
+        }
+    }
+}
 
-    Ok(large_dirs.iter().map(|d| d.get_size()).sum())
+/// Decides what to delete to reach a required amount of free space on a disk
+struct DiskPlanner<'a> {
+    tree: &'a FileSystemTree,
+    disk_size: usize,
+    required_free: usize,
+}
+
+impl<'a> DiskPlanner<'a> {
+    fn new(
+        tree: &'a FileSystemTree,
+        disk_size: usize,
+        required_free: usize,
+    ) -> Result<Self, PlanningError> {
+        let used = tree.root().size;
+        if used > disk_size {
+            return Err(PlanningError::UsedExceedsDisk { used, disk_size });
+        }
+        Ok(DiskPlanner {
+            tree,
+            disk_size,
+            required_free,
+        })
+    }
+
+    fn free(&self) -> usize {
+        self.disk_size - self.tree.root().size
+    }
+
+    fn need_to_free(&self) -> usize {
+        self.required_free.saturating_sub(self.free())
+    }
+
+    fn plan_with<F>(&self, choose: F) -> CleanupPlan<'a>
+    where
+        F: FnOnce(usize) -> Vec<&'a TreeNode>,
+    {
+        let need_to_free = self.need_to_free();
+        if need_to_free == 0 {
+            CleanupPlan::NothingToDelete { free: self.free() }
+        } else if need_to_free > self.tree.root().size {
+            CleanupPlan::Impossible { need_to_free }
+        } else {
+            CleanupPlan::Delete {
+                need_to_free,
+                directories: choose(need_to_free),
+            }
+        }
+    }
+
+    /// Delete the smallest single directory that frees enough space
+    fn single_directory(&self) -> CleanupPlan<'a> {
+        let tree = self.tree;
+        self.plan_with(|need| tree.smallest_directory_freeing(need).into_iter().collect())
+    }
+
+    /// Delete a set of directories that frees enough space while deleting as few bytes as
+    /// possible, which can be less than with `single_directory` and is never more.
+    /// Sets that do not free enough yet are only told apart by their size in buckets of
+    /// 1/`PLANNER_BUCKETS` of the space that needs to be freed, so once that is more than
+    /// `PLANNER_BUCKETS` bytes a slightly cheaper set can be missed.
+    fn minimal_set(&self) -> CleanupPlan<'a> {
+        let tree = self.tree;
+        self.plan_with(
+            |need| match SetPlanner::new(tree, need, PLANNER_BUCKETS).solve() {
+                Some(chosen) => chosen.into_iter().map(|idx| &tree.nodes[idx]).collect(),
+                None => tree.smallest_directory_freeing(need).into_iter().collect(),
+            },
+        )
+    }
+}
+
+/// Tree knapsack behind `DiskPlanner::minimal_set`.
+/// Every directory is worth its size in buckets, rounded down. For each directory and worth, the
+/// planner keeps the fewest bytes that deleting disjoint directories of that subtree takes to
+/// reach it. Sets that free enough space all share the extra slot `buckets`, so they never
+/// compete with cheaper sets that fall short. Merging a subdirectory costs at most the product
+/// of both table lengths, which keeps the whole run polynomial in the number of directories and
+/// buckets.
+struct SetPlanner<'a> {
+    tree: &'a FileSystemTree,
+    need: usize,
+    bucket_size: usize,
+    buckets: usize,
+    /// Per directory, the table before and after merging each of its subdirectories in turn
+    merged: Vec<Vec<Vec<usize>>>,
+    /// Per directory, the final table, which includes deleting the directory itself
+    best: Vec<Vec<usize>>,
+}
+
+impl<'a> SetPlanner<'a> {
+    fn new(tree: &'a FileSystemTree, need: usize, buckets: usize) -> Self {
+        let mut planner = SetPlanner {
+            tree,
+            need,
+            bucket_size: need.div_ceil(buckets).max(1),
+            buckets,
+            merged: vec![Vec::new(); tree.nodes.len()],
+            best: vec![Vec::new(); tree.nodes.len()],
+        };
+        // Children come after their parents, so going backwards visits them first
+        for idx in (0..tree.nodes.len()).rev() {
+            if tree.nodes[idx].is_dir {
+                planner.fill(idx);
+            }
+        }
+        planner
+    }
+
+    fn worth(&self, idx: usize) -> usize {
+        self.slot(
+            self.tree.nodes[idx].size / self.bucket_size,
+            self.tree.nodes[idx].size,
+        )
+    }
+
+    /// Table slot of a set of directories with the given worth and size. Sets that fall short
+    /// are worth less than `buckets`, as the bucket size is rounded up.
+    fn slot(&self, worth: usize, bytes: usize) -> usize {
+        if bytes >= self.need {
+            self.buckets
+        } else {
+            worth
+        }
+    }
+
+    fn subdirectories(&self, idx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.tree.nodes[idx]
+            .children
+            .iter()
+            .copied()
+            .filter(|&c| self.tree.nodes[c].is_dir)
+    }
+
+    fn fill(&mut self, idx: usize) {
+        let mut steps = vec![vec![0]];
+        for child in self.subdirectories(idx).collect::<Vec<_>>() {
+            let current = steps.last().unwrap();
+            let other = &self.best[child];
+            let mut next = vec![usize::MAX; self.buckets + 1];
+            for (a, &bytes_a) in current.iter().enumerate() {
+                if bytes_a == usize::MAX {
+                    continue;
+                }
+                for (b, &bytes_b) in other.iter().enumerate() {
+                    if bytes_b != usize::MAX {
+                        let bytes = bytes_a + bytes_b;
+                        let slot = &mut next[self.slot(a + b, bytes)];
+                        *slot = (*slot).min(bytes);
+                    }
+                }
+            }
+            while next.last() == Some(&usize::MAX) {
+                next.pop();
+            }
+            steps.push(next);
+        }
+
+        let mut best = steps.last().unwrap().clone();
+        let worth = self.worth(idx);
+        if best.len() <= worth {
+            best.resize(worth + 1, usize::MAX);
+        }
+        best[worth] = best[worth].min(self.tree.nodes[idx].size);
+        self.merged[idx] = steps;
+        self.best[idx] = best;
+    }
+
+    /// Indices of the directories to delete, in tree order, or `None` if not even deleting
+    /// everything frees enough
+    fn solve(&self) -> Option<Vec<usize>> {
+        if self.best[0]
+            .get(self.buckets)
+            .is_none_or(|&bytes| bytes == usize::MAX)
+        {
+            return None;
+        }
+        let mut chosen = Vec::new();
+        self.collect(0, self.buckets, &mut chosen);
+        chosen.sort_unstable();
+        debug_assert!(chosen
+            .iter()
+            .tuple_combinations()
+            .all(|(&a, &b)| !self.tree.nested(a, b)));
+        Some(chosen)
+    }
+
+    /// Retrace which directories below `idx` make up the entry for `worth`
+    fn collect(&self, idx: usize, mut worth: usize, chosen: &mut Vec<usize>) {
+        let bytes = self.best[idx][worth];
+        if bytes == 0 {
+            return;
+        }
+        if worth == self.worth(idx) && bytes == self.tree.nodes[idx].size {
+            chosen.push(idx);
+            return;
+        }
+        let steps = &self.merged[idx];
+        let children = self.subdirectories(idx).collect::<Vec<_>>();
+        for (step, &child) in children.iter().enumerate().rev() {
+            let (before, after) = (&steps[step], &steps[step + 1]);
+            let other = &self.best[child];
+            let target = after[worth];
+            let (a, b) = (0..before.len())
+                .flat_map(|a| (0..other.len()).map(move |b| (a, b)))
+                .find(|&(a, b)| {
+                    before[a] != usize::MAX
+                        && other[b] != usize::MAX
+                        && before[a] + other[b] == target
+                        && self.slot(a + b, target) == worth
+                })
+                .expect("Every table entry stems from a merge");
+            self.collect(child, b, chosen);
+            worth = a;
+        }
+    }
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let fs_state = observe_commands(
-        stream_items_from_file::<P, CommandOrListing>(input)?
-            .map(|r| r.expect("Invalid line in input")),
-    );
-
-    let current_used_space = fs_state.get_size();
-    const TOTAL_AVAILABLE: usize = 70000000;
-    const REQUIRED: usize = 30000000;
-    let current_free = TOTAL_AVAILABLE - current_used_space;
-    let need_to_free_up = REQUIRED - current_free;
-
-    let mut could_delete = Vec::new();
-    fs_state.find_elements(
-        &|e| e.is_dir() && e.get_size() >= need_to_free_up,
-        &mut could_delete,
-    );
-
-    Ok(could_delete
-        .iter()
-        .map(|d| d.get_size())
-        .min()
-        .expect("No suitable directory found"))
+    let tree = FileSystemTree::new(&read_file_system(input)?);
+    match DiskPlanner::new(&tree, DISK_SIZE, REQUIRED_FREE)?.single_directory() {
+        CleanupPlan::NothingToDelete { .. } => Ok(0),
+        plan @ CleanupPlan::Delete { .. } => Ok(plan.freed()),
+        plan @ CleanupPlan::Impossible { .. } => Err(anyhow!("{}", plan)),
+    }
 }
 
 fn main() -> Result<()> {
-    println!("Answer for part 1: {}", part1(INPUT)?);
-    println!("Answer for part 2: {}", part2(INPUT)?);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let Some(mode) = args.first() else {
+        println!("Answer for part 1: {}", part1(INPUT)?);
+        println!("Answer for part 2: {}", part2(INPUT)?);
+        return Ok(());
+    };
+
+    let tree = FileSystemTree::new(&read_file_system(INPUT)?);
+    let size = || -> Result<usize> {
+        Ok(args
+            .get(1)
+            .ok_or_else(|| anyhow!("Missing size argument"))?
+            .parse()?)
+    };
+    let nodes = match mode.as_str() {
+        "du" => {
+            println!("{}", tree.du());
+            return Ok(());
+        }
+        "tree" => {
+            println!("{}", tree.tree());
+            return Ok(());
+        }
+        "below" => tree.directories_below(size()?).collect(),
+        "above" => tree.directories_above(size()?).collect(),
+        "largest-files" => tree.largest_files(size()?),
+        "freeing" => tree
+            .smallest_directory_freeing(size()?)
+            .into_iter()
+            .collect(),
+        "plan" => {
+            let number = |idx: usize, default: usize| -> Result<usize> {
+                Ok(args
+                    .get(idx)
+                    .map(|a| a.parse())
+                    .transpose()?
+                    .unwrap_or(default))
+            };
+            let planner =
+                DiskPlanner::new(&tree, number(1, DISK_SIZE)?, number(2, REQUIRED_FREE)?)?;
+            println!("Single directory: {}", planner.single_directory());
+            println!("Minimal set: {}", planner.minimal_set());
+            return Ok(());
+        }
+        _ => return Err(anyhow!("Unknown mode '{}'", mode)),
+    };
+    for node in nodes {
+        println!("{}\t{}", node.size, node.path);
+    }
 
     Ok(())
 }
@@ -245,7 +807,7 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2022::test_helpers::create_example_file;
+    use advent_of_code_2022::test_helpers::{create_example_file, TestRng};
     use indoc::indoc;
 
     #[test]
@@ -281,7 +843,277 @@ mod tests {
             None,
         );
         assert_eq!(part1(&file).unwrap(), 95437);
-        //assert_eq!(part2(&file).unwrap(), 19);
+        assert_eq!(part2(&file).unwrap(), 24933642);
         drop(dir);
     }
+
+    #[test]
+    fn test_d07_transcript_interpreter() {
+        let transcript = indoc! {"
+            $ cd /a/b
+            $ ls
+            10 x
+            $ cd ../../c/./d
+            $ ls
+            20 y
+            $ cd /
+            $ ls
+            dir a
+            dir c
+            5 z
+            $ ls
+            5 z
+            dir c
+            dir a
+            $ cd a/b
+            $ ls
+            10 x
+        "};
+        let fs = observe_commands(transcript.lines()).unwrap();
+        assert_eq!(FileSystemTree::new(&fs).root().size, 35);
+
+        let error = |transcript: &str| observe_commands(transcript.lines()).unwrap_err();
+        assert!(matches!(
+            error("$ cd /\n$ cd .."),
+            TranscriptError::AboveRoot { line: 2 }
+        ));
+        assert!(matches!(
+            error("$ ls\n1 f\n$ cd f"),
+            TranscriptError::NotADirectory { line: 3, .. }
+        ));
+        assert!(matches!(
+            error("$ ls\n1 f\n$ cd g"),
+            TranscriptError::NoSuchDirectory { line: 3, .. }
+        ));
+        assert!(matches!(
+            error("$ cd /\n1 f"),
+            TranscriptError::UnexpectedListing { line: 2 }
+        ));
+        assert!(matches!(
+            error("$ cd /\n$ rm -rf f"),
+            TranscriptError::Parse { line: 2, .. }
+        ));
+        assert!(matches!(
+            error("$ ls\n1 f\n$ ls\n2 f"),
+            TranscriptError::ContradictingListing { line: 3, .. }
+        ));
+        assert!(matches!(
+            error("$ ls\n1 f\n$ ls\n1 f\n1 f"),
+            TranscriptError::ContradictingListing { line: 3, .. }
+        ));
+        assert_eq!(
+            error("$ cd a\n$ cd ..\n$ ls\n1 f").to_string(),
+            "Line 3: listing of / contradicts what is known ('a' is missing)"
+        );
+        assert!(matches!(
+            error("$ ls\ndir a\n$ ls\ndir a\n3 b"),
+            TranscriptError::ContradictingListing { line: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_d07_tree_queries() {
+        let transcript = indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            14848514 b.txt
+            8504156 c.dat
+            dir d
+            $ cd a
+            $ ls
+            dir e
+            29116 f
+            2557 g
+            62596 h.lst
+            $ cd e
+            $ ls
+            584 i
+            $ cd /d
+            $ ls
+            4060174 j
+            8033020 d.log
+            5626152 d.ext
+            7214296 k
+        "};
+        let tree = FileSystemTree::new(&observe_commands(transcript.lines()).unwrap());
+        assert_eq!(tree.root().size, 48381165);
+        let paths =
+            |nodes: Vec<&TreeNode>| nodes.iter().map(|n| n.path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths(tree.directories_below(100000).collect()),
+            vec!["/a", "/a/e"]
+        );
+        assert_eq!(paths(tree.directories_above(25000000).collect()), vec!["/"]);
+        assert_eq!(paths(tree.largest_files(2)), vec!["/b.txt", "/c.dat"]);
+        assert_eq!(tree.smallest_directory_freeing(8381165).unwrap().path, "/d");
+        assert!(tree.smallest_directory_freeing(50000000).is_none());
+        assert_eq!(tree.du(), "584\t/a/e\n94853\t/a\n24933642\t/d\n48381165\t/");
+        assert_eq!(
+            tree.tree().lines().take(4).collect::<Vec<_>>(),
+            vec![
+                "- / (dir, size=48381165)",
+                "  - a (dir, size=94853)",
+                "    - e (dir, size=584)",
+                "      - i (file, size=584)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_d07_disk_planner() {
+        let transcript = indoc! {"
+            $ cd /
+            $ ls
+            dir x
+            dir y
+            dir z
+            1 f
+            $ cd x
+            $ ls
+            50 a
+            $ cd ../y
+            $ ls
+            40 b
+            dir w
+            $ cd w
+            $ ls
+            5 c
+            $ cd /z
+            $ ls
+            30 d
+        "};
+        let tree = FileSystemTree::new(&observe_commands(transcript.lines()).unwrap());
+        assert_eq!(tree.root().size, 126);
+        fn paths<'a>(plan: &CleanupPlan<'a>) -> Vec<&'a str> {
+            match plan {
+                CleanupPlan::Delete { directories, .. } => {
+                    directories.iter().map(|d| d.path.as_str()).collect()
+                }
+                _ => Vec::new(),
+            }
+        }
+
+        // 124 bytes are free, so 55 more have to be freed, which no directory but the root can
+        let planner = DiskPlanner::new(&tree, 250, 179).unwrap();
+        assert_eq!(paths(&planner.single_directory()), vec!["/"]);
+        let plan = planner.minimal_set();
+        assert_eq!(paths(&plan), vec!["/x", "/y/w"]);
+        assert_eq!(plan.freed(), 55);
+
+        let planner = DiskPlanner::new(&tree, 250, 100).unwrap();
+        assert!(matches!(
+            planner.single_directory(),
+            CleanupPlan::NothingToDelete { free: 124 }
+        ));
+        assert_eq!(
+            planner.minimal_set().to_string(),
+            "Nothing to delete, 124 bytes are already free"
+        );
+        assert!(matches!(
+            DiskPlanner::new(&tree, 250, 251).unwrap().minimal_set(),
+            CleanupPlan::Impossible { need_to_free: 127 }
+        ));
+        assert!(DiskPlanner::new(&tree, 100, 10).is_err());
+
+        // Deleting /a frees fewer bytes than the root but falls into the same bucket
+        let transcript = indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            1 f
+            $ cd a
+            $ ls
+            1024 g
+        "};
+        let tree = FileSystemTree::new(&observe_commands(transcript.lines()).unwrap());
+        let plan = DiskPlanner::new(&tree, 1025, 1025).unwrap().minimal_set();
+        assert_eq!(paths(&plan), vec!["/"]);
+        assert_eq!(plan.freed(), 1025);
+    }
+
+    /// A transcript of a random tree of `dirs` directories below the root, each holding one or
+    /// two files of up to `max_file` bytes
+    fn random_transcript(rng: &mut TestRng, dirs: usize, max_file: usize) -> String {
+        let mut children = vec![Vec::new(); dirs + 1];
+        for dir in 1..=dirs {
+            children[rng.below(dir)].push(dir);
+        }
+        let mut lines = vec!["$ cd /".to_string()];
+        fn visit(
+            dir: usize,
+            children: &[Vec<usize>],
+            rng: &mut TestRng,
+            max_file: usize,
+            lines: &mut Vec<String>,
+        ) {
+            lines.push("$ ls".to_string());
+            lines.extend(children[dir].iter().map(|c| format!("dir d{}", c)));
+            for file in 0..1 + rng.below(2) {
+                lines.push(format!("{} f{}", 1 + rng.below(max_file), file));
+            }
+            for &child in &children[dir] {
+                lines.push(format!("$ cd d{}", child));
+                visit(child, children, rng, max_file, lines);
+                lines.push("$ cd ..".to_string());
+            }
+        }
+        visit(0, &children, rng, max_file, &mut lines);
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_d07_minimal_set_is_optimal() {
+        let mut rng = TestRng::new(7);
+        for _ in 0..20 {
+            let transcript = random_transcript(&mut rng, 12, 100);
+            let tree = FileSystemTree::new(&observe_commands(transcript.lines()).unwrap());
+            let used = tree.root().size;
+            let planner = DiskPlanner::new(&tree, used, used / 3).unwrap();
+            let need = planner.need_to_free();
+
+            // Try every set of disjoint directories
+            let dirs = (0..tree.nodes.len())
+                .filter(|&idx| tree.nodes[idx].is_dir)
+                .collect::<Vec<_>>();
+            let optimum = (0..1usize << dirs.len())
+                .filter_map(|mask| {
+                    let set = dirs
+                        .iter()
+                        .enumerate()
+                        .filter(|(bit, _)| mask & (1 << bit) != 0)
+                        .map(|(_, &idx)| idx)
+                        .collect::<Vec<_>>();
+                    let disjoint = set
+                        .iter()
+                        .tuple_combinations()
+                        .all(|(&a, &b)| !tree.nested(a, b));
+                    let freed = set.iter().map(|&idx| tree.nodes[idx].size).sum::<usize>();
+                    (disjoint && freed >= need).then_some(freed)
+                })
+                .min()
+                .unwrap();
+            assert_eq!(planner.minimal_set().freed(), optimum);
+        }
+    }
+
+    #[test]
+    fn test_d07_minimal_set_large_tree() {
+        let mut rng = TestRng::new(45);
+        let transcript = random_transcript(&mut rng, 400, 300_000);
+        let tree = FileSystemTree::new(&observe_commands(transcript.lines()).unwrap());
+        assert_eq!(tree.directories().count(), 401);
+        let used = tree.root().size;
+        let planner = DiskPlanner::new(&tree, used, used / 2).unwrap();
+        let need = planner.need_to_free();
+        assert!(need > 0);
+
+        let plan = planner.minimal_set();
+        let CleanupPlan::Delete { directories, .. } = &plan else {
+            panic!("Expected directories to delete, got {}", plan);
+        };
+        assert!(plan.freed() >= need);
+        assert!(plan.freed() <= planner.single_directory().freed());
+        assert!(directories.iter().all(|d| d.size > 0));
+    }
 }
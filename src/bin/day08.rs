@@ -1,9 +1,13 @@
+use advent_of_code_2022::{
+    direction::Direction4,
+    field2d::Field2D,
+    heatmap::{self, ImageFormat},
+};
 use std::{
-    cmp,
     fs::File,
-    io::{BufRead, BufReader},
-    iter,
+    io::{self, BufRead, BufReader},
     path::Path,
+    str::FromStr,
 };
 use thiserror::Error;
 
@@ -11,131 +15,217 @@ use anyhow::Result;
 
 const INPUT: &str = "input/day08.txt";
 
-#[derive(Debug)]
-struct Field2D<T> {
-    entries: Vec<T>,
-    width: usize,
-}
-
 #[derive(Debug, Error)]
 enum Field2dParseError {
     #[error("Empty input")]
     EmptyInput,
+    #[error("Invalid tree height '{height}' in line {line}, column {column}")]
+    InvalidHeight {
+        line: usize,
+        column: usize,
+        height: char,
+    },
+    #[error("Line {line} has {len} trees, expected {width}")]
+    RaggedRow {
+        line: usize,
+        len: usize,
+        width: usize,
+    },
 }
 
-// TODO: Check if we could reuse the field impl from last year here
-impl Field2D<u8> {
-    fn from_lines(lines: impl Iterator<Item = String>) -> Result<Self, Field2dParseError> {
-        let mut lines = lines.peekable();
-        let width = lines.peek().ok_or(Field2dParseError::EmptyInput)?.len();
-        let entries = lines
-            .flat_map(|line| {
-                line.chars()
-                    // TODO: Don't panic
-                    .map(|c| c.to_digit(10).expect("Could not parse") as u8)
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
+type TreeMap = Field2D<u8>;
 
-        Ok(Self { entries, width })
+fn parse_tree_map(lines: impl Iterator<Item = String>) -> Result<TreeMap, Field2dParseError> {
+    let mut rows = Vec::new();
+    for (line_idx, line) in lines.enumerate() {
+        let row = line
+            .chars()
+            .enumerate()
+            .map(|(column, c)| {
+                c.to_digit(10)
+                    .map(|h| h as u8)
+                    .ok_or(Field2dParseError::InvalidHeight {
+                        line: line_idx + 1,
+                        column: column + 1,
+                        height: c,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = rows.first().map(Vec::len) {
+            if row.len() != first {
+                return Err(Field2dParseError::RaggedRow {
+                    line: line_idx + 1,
+                    len: row.len(),
+                    width: first,
+                });
+            }
+        }
+        rows.push(row);
     }
+    if rows.first().is_none_or(Vec::is_empty) {
+        return Err(Field2dParseError::EmptyInput);
+    }
+    Field2D::parse(rows.into_iter(), |row| row).ok_or(Field2dParseError::EmptyInput)
+}
+
+fn read_tree_map<P: AsRef<Path>>(input: P) -> Result<TreeMap> {
+    let lines = BufReader::new(File::open(input)?)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(parse_tree_map(lines.into_iter())?)
+}
+
+/// What a single tree sees when looking into one direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct View {
+    /// No tree of the same height or taller blocks the view up to the edge
+    to_edge: bool,
+    /// Number of trees visible up to and including the first blocking one
+    distance: usize,
+}
+
+/// The positions of every row or column, ordered from the edge a tree looking `towards` faces
+/// to the opposite edge
+fn sight_lines(
+    width: usize,
+    height: usize,
+    towards: Direction4,
+) -> impl Iterator<Item = impl Iterator<Item = (usize, usize)>> {
+    let (lines, len) = if towards.is_horizontal() {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    (0..lines).map(move |line| {
+        (0..len).map(move |idx| {
+            let along = match towards {
+                Direction4::Left | Direction4::Up => idx,
+                Direction4::Right | Direction4::Down => len - 1 - idx,
+            };
+            if towards.is_horizontal() {
+                (along, line)
+            } else {
+                (line, along)
+            }
+        })
+    })
 }
 
-impl<T> Field2D<T> {
-    fn get(&self, x: usize, y: usize) -> Option<&T> {
-        if x < self.width {
-            let idx = y * self.width + x;
-            self.entries.get(idx)
-        } else {
-            None
+/// Report the view of every tree looking `towards` one direction to `observe`.
+/// Each sight line is walked once, starting at the edge the trees look at. A stack keeps the
+/// trees that could still block the view of later ones, with heights decreasing towards the top,
+/// so every tree is pushed and popped at most once.
+fn survey<F: FnMut((usize, usize), View)>(trees: &TreeMap, towards: Direction4, mut observe: F) {
+    let mut blocking: Vec<(usize, u8)> = Vec::new();
+    for line in sight_lines(trees.width(), trees.height(), towards) {
+        blocking.clear();
+        for (idx, pos) in line.enumerate() {
+            let height = trees[pos];
+            while blocking.last().is_some_and(|&(_, h)| h < height) {
+                blocking.pop();
+            }
+            let view = match blocking.last() {
+                Some(&(b, _)) => View {
+                    to_edge: false,
+                    distance: idx - b,
+                },
+                None => View {
+                    to_edge: true,
+                    distance: idx,
+                },
+            };
+            observe(pos, view);
+            blocking.push((idx, height));
         }
     }
+}
 
-    fn width(&self) -> usize {
-        self.width
+/// Whether each tree can be seen from outside the forest
+fn visibility(trees: &TreeMap) -> Field2D<bool> {
+    let mut visible = Field2D::new_with_value(trees.width(), trees.height(), false);
+    for towards in Direction4::all() {
+        survey(trees, towards, |pos, view| visible[pos] |= view.to_edge);
     }
+    visible
+}
 
-    fn height(&self) -> usize {
-        self.entries.len() / self.width
+/// The product of the viewing distances into all four directions of each tree
+fn scenic_scores(trees: &TreeMap) -> Field2D<usize> {
+    let mut scores = Field2D::new_with_value(trees.width(), trees.height(), 1);
+    for towards in Direction4::all() {
+        survey(trees, towards, |pos, view| scores[pos] *= view.distance);
     }
+    scores
 }
 
-type TreeMap = Field2D<u8>;
+/// The grids that can be exported as heatmaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Heights,
+    Visibility,
+    ScenicScores,
+}
 
-fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let field = TreeMap::from_lines(
-        BufReader::new(File::open(input)?)
-            .lines()
-            .map(|ml| ml.expect("Could not read line")),
-    )?;
-
-    let mut count = 2 * field.width() + 2 * field.height() - 4;
-    for x in 1..field.width() - 1 {
-        for y in 1..field.height() - 1 {
-            let mut any_dir_visible = false;
-            for dir in &[-1, 1] {
-                any_dir_visible |= iter::repeat(x)
-                    .enumerate()
-                    .map(|(i, x)| (x as i32 + ((i + 1) as i32 * dir)))
-                    .take_while(|nx| *nx >= 0 && *nx < field.width() as i32)
-                    .map(|nx| nx as usize)
-                    .all(|nx| field.get(nx, y).unwrap() < field.get(x, y).unwrap());
-            }
-            for dir in &[-1, 1] {
-                any_dir_visible |= iter::repeat(y)
-                    .enumerate()
-                    .map(|(i, y)| (y as i32 + (i + 1) as i32 * dir))
-                    .take_while(|ny| *ny >= 0 && *ny < field.height() as i32)
-                    .map(|ny| ny as usize)
-                    .all(|ny| field.get(x, ny).unwrap() < field.get(x, y).unwrap());
-            }
-            if any_dir_visible {
-                count += 1
-            }
+#[derive(Debug, Error)]
+enum LayerParseError {
+    #[error("Unknown layer '{0}', expected heights, visibility or scores")]
+    UnknownLayer(String),
+}
+
+impl FromStr for Layer {
+    type Err = LayerParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "heights" => Ok(Layer::Heights),
+            "visibility" => Ok(Layer::Visibility),
+            "scores" => Ok(Layer::ScenicScores),
+            _ => Err(LayerParseError::UnknownLayer(s.to_string())),
         }
     }
-    Ok(count)
 }
 
-fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let field = TreeMap::from_lines(
-        BufReader::new(File::open(input)?)
-            .lines()
-            .map(|ml| ml.expect("Could not read line")),
-    )?;
-    let mut max_score = 1;
-    for x in 1..field.width() - 1 {
-        for y in 1..field.height() - 1 {
-            let mut score = 1;
-            for dir in &[-1, 1] {
-                let xr = iter::repeat(x)
-                    .enumerate()
-                    .map(|(i, x)| x as i32 + ((i + 1) as i32 * dir))
-                    .take_while(|nx| {
-                        *nx >= 1
-                            && *nx < field.width() as i32 - 1
-                            && field.get(*nx as usize, y).unwrap() < field.get(x, y).unwrap()
-                    })
-                    .count() + 1;
-                let yr = iter::repeat(y)
-                    .enumerate()
-                    .map(|(i, y)| y as i32 + ((i + 1) as i32 * dir))
-                    .take_while(|ny| {
-                        *ny >= 1
-                            && *ny < field.height() as i32 - 1
-                            && field.get(x, *ny as usize).unwrap() < field.get(x, y).unwrap()
-                    })
-                    .count() + 1;
-
-                score *= xr * yr;
+impl Layer {
+    fn intensities(self, trees: &TreeMap) -> Field2D<u8> {
+        match self {
+            // Keep the absolute heights so that images of different forests are comparable
+            Layer::Heights => trees.map(|&h| (h as usize * 255 / 9) as u8),
+            Layer::Visibility => visibility(trees).map(|&v| if v { 255 } else { 0 }),
+            // Scores span several orders of magnitude, a log scale keeps the low ones apart
+            Layer::ScenicScores => {
+                heatmap::normalize(&scenic_scores(trees), |&s| (s as f64).ln_1p())
             }
-            max_score = cmp::max(score, max_score);
         }
     }
-    Ok(max_score)
+}
+
+fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
+    let trees = read_tree_map(input)?;
+    Ok(visibility(&trees).iter().filter(|v| **v).count())
+}
+
+fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
+    let trees = read_tree_map(input)?;
+    Ok(scenic_scores(&trees)
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default())
 }
 
 fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("export") {
+        let layer = args.get(1).map_or(Ok(Layer::ScenicScores), |l| l.parse())?;
+        let format = args.get(2).map_or(Ok(ImageFormat::Ansi), |f| f.parse())?;
+        let intensities = layer.intensities(&read_tree_map(INPUT)?);
+        match args.get(3) {
+            Some(path) => heatmap::render(&intensities, format, File::create(path)?)?,
+            None => heatmap::render(&intensities, format, io::stdout().lock())?,
+        }
+        return Ok(());
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2: {}", part2(INPUT)?);
 
@@ -145,7 +235,7 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2022::test_helpers::create_example_file;
+    use advent_of_code_2022::test_helpers::{create_example_file, TestRng};
     use indoc::indoc;
 
     #[test]
@@ -164,4 +254,107 @@ mod tests {
         assert_eq!(part2(&file).unwrap(), 8);
         drop(dir);
     }
+
+    #[test]
+    fn test_d08_layers() {
+        let trees = parse_tree_map(["303", "353", "303"].into_iter().map(String::from)).unwrap();
+        let intensities = |layer: Layer| layer.intensities(&trees).into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            intensities(Layer::Heights),
+            [85, 0, 85, 85, 141, 85, 85, 0, 85]
+        );
+        assert_eq!(
+            intensities(Layer::Visibility),
+            [255, 255, 255, 255, 255, 255, 255, 255, 255]
+        );
+        assert_eq!(
+            intensities(Layer::ScenicScores),
+            [0, 0, 0, 0, 255, 0, 0, 0, 0]
+        );
+
+        let mut pgm = Vec::new();
+        heatmap::render(
+            &Layer::Heights.intensities(&trees),
+            ImageFormat::Pgm,
+            &mut pgm,
+        )
+        .unwrap();
+        assert!(pgm.starts_with(b"P5\n3 3\n255\n"));
+        assert!("trees".parse::<Layer>().is_err());
+    }
+
+    /// Walk outwards from every tree, the way the puzzle describes it
+    fn naive_views(trees: &TreeMap) -> (Field2D<bool>, Field2D<usize>) {
+        let mut visible = Field2D::new_with_value(trees.width(), trees.height(), false);
+        let mut scores = Field2D::new_with_value(trees.width(), trees.height(), 1);
+        for ((x, y), &height) in trees.iter_with_position() {
+            for towards in Direction4::all() {
+                let delta = towards.delta();
+                let (mut nx, mut ny) = (x as isize, y as isize);
+                let mut distance = 0;
+                let mut to_edge = true;
+                loop {
+                    nx += delta.x;
+                    ny += delta.y;
+                    if nx < 0
+                        || ny < 0
+                        || nx as usize >= trees.width()
+                        || ny as usize >= trees.height()
+                    {
+                        break;
+                    }
+                    distance += 1;
+                    if trees[(nx as usize, ny as usize)] >= height {
+                        to_edge = false;
+                        break;
+                    }
+                }
+                visible[(x, y)] |= to_edge;
+                scores[(x, y)] *= distance;
+            }
+        }
+        (visible, scores)
+    }
+
+    #[test]
+    fn test_d08_views() {
+        let trees = parse_tree_map(
+            ["30373", "25512", "65332", "33549", "35390"]
+                .into_iter()
+                .map(String::from),
+        )
+        .unwrap();
+        let visible = visibility(&trees);
+        assert!(visible[(1, 1)]);
+        assert!(!visible[(3, 1)]);
+        let scores = scenic_scores(&trees);
+        assert_eq!(scores[(2, 1)], 4);
+        assert_eq!(scores[(2, 3)], 8);
+        assert_eq!(scores[(0, 2)], 0);
+
+        let mut rng = TestRng::new(8);
+        let rows = (0..23).map(|_| (0..37).map(|_| rng.below(10) as u8).collect::<Vec<_>>());
+        let forest = Field2D::parse(rows, |row| row).unwrap();
+        assert_eq!(
+            (visibility(&forest), scenic_scores(&forest)),
+            naive_views(&forest)
+        );
+
+        assert!(matches!(
+            parse_tree_map(["123", "1x3"].into_iter().map(String::from)),
+            Err(Field2dParseError::InvalidHeight {
+                line: 2,
+                column: 2,
+                height: 'x'
+            })
+        ));
+        assert!(matches!(
+            parse_tree_map(["123", "12"].into_iter().map(String::from)),
+            Err(Field2dParseError::RaggedRow { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_tree_map(std::iter::empty()),
+            Err(Field2dParseError::EmptyInput)
+        ));
+    }
 }
@@ -1,10 +1,9 @@
-use advent_of_code_2022::stream_items_from_file;
-use std::{
-    collections::HashSet,
-    num::ParseIntError,
-    path::Path,
-    str::FromStr,
+use advent_of_code_2022::{
+    direction::{Direction4, DirectionParseError},
+    geometry::Point2,
+    stream_items_from_file,
 };
+use std::{collections::HashSet, fmt::Display, iter, num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
 
 use anyhow::Result;
@@ -12,53 +11,7 @@ use anyhow::Result;
 const INPUT: &str = "input/day09.txt";
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-    UpRight,
-    UpLeft,
-    DownLeft,
-    DownRight,
-}
-
-#[derive(Debug, Error)]
-enum DirectionParseError {
-    #[error("Invalid Direction '{0}'")]
-    InvalidDirection(String),
-}
-
-impl FromStr for Direction {
-    type Err = DirectionParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "U" => Ok(Direction::Up),
-            "D" => Ok(Direction::Down),
-            "L" => Ok(Direction::Left),
-            "R" => Ok(Direction::Right),
-            _ => Err(DirectionParseError::InvalidDirection(s.to_string())),
-        }
-    }
-}
-
-impl Direction {
-    fn apply(&self, loc: &Location) -> Location {
-        match self {
-            Direction::Up => Location(loc.0, loc.1 + 1),
-            Direction::Down => Location(loc.0, loc.1 - 1),
-            Direction::Left => Location(loc.0 - 1, loc.1),
-            Direction::Right => Location(loc.0 + 1, loc.1),
-            Direction::UpRight => Location(loc.0 + 1, loc.1 + 1),
-            Direction::UpLeft => Location(loc.0 - 1, loc.1 + 1),
-            Direction::DownLeft => Location(loc.0 - 1, loc.1 - 1),
-            Direction::DownRight => Location(loc.0 + 1, loc.1 - 1),
-        }
-    }
-}
-
-struct MovementInstruction(Direction, usize);
+struct MovementInstruction(Direction4, usize);
 
 #[derive(Debug, Error)]
 enum MovementInstructionParseError {
@@ -80,86 +33,201 @@ impl FromStr for MovementInstruction {
     }
 }
 
+impl Display for MovementInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dir = match self.0 {
+            Direction4::Up => 'U',
+            Direction4::Right => 'R',
+            Direction4::Down => 'D',
+            Direction4::Left => 'L',
+        };
+        write!(f, "{} {}", dir, self.1)
+    }
+}
+
 impl MovementInstruction {
-    fn unfold(self) -> Vec<Direction> {
-        vec![self.0; self.1]
+    fn unfold(self) -> impl Iterator<Item = Direction4> {
+        iter::repeat_n(self.0, self.1)
     }
 }
 
-#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
-struct Location(isize, isize);
+type Location = Point2<isize>;
 
-impl Location {
-    fn touches(&self, other: &Self) -> bool {
-        self.0.abs_diff(other.0) <= 1 && self.1.abs_diff(other.1) <= 1
+/// A rope made of knots that all start at the origin. The first knot is the head, every other
+/// knot follows the one in front of it.
+#[derive(Debug, Clone)]
+struct Rope {
+    knots: Vec<Location>,
+    visited: Vec<HashSet<Location>>,
+}
+
+impl Rope {
+    fn new(len: usize) -> Self {
+        assert!(len > 0, "A rope needs at least one knot");
+        Rope {
+            knots: vec![Location::default(); len],
+            visited: vec![HashSet::from([Location::default()]); len],
+        }
+    }
+
+    /// Move the head one step and let the rest of the rope follow
+    fn step(&mut self, dir: Direction4) {
+        self.knots[0] += dir.delta();
+        self.visited[0].insert(self.knots[0]);
+        for idx in 1..self.knots.len() {
+            let (ahead, knot) = (self.knots[idx - 1], self.knots[idx]);
+            if ahead.chebyshev_distance(&knot) <= 1 {
+                // Knots further back only move if this one did
+                break;
+            }
+            self.knots[idx] = knot + (ahead - knot).signum();
+            self.visited[idx].insert(self.knots[idx]);
+        }
+    }
+
+    fn knots(&self) -> &[Location] {
+        &self.knots
+    }
+
+    /// Every location the knot with the given index has been at, including the origin
+    fn visited(&self, knot: usize) -> &HashSet<Location> {
+        &self.visited[knot]
     }
+
+    fn tail_visited(&self) -> &HashSet<Location> {
+        self.visited(self.knots.len() - 1)
+    }
+}
+
+fn read_instructions<P: AsRef<Path>>(input: P) -> Result<Vec<MovementInstruction>> {
+    Ok(stream_items_from_file::<P, MovementInstruction>(input)?.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn simulate_rope<P: AsRef<Path>>(input: P, len: usize) -> Result<Rope> {
+    let mut rope = Rope::new(len);
+    for instruction in read_instructions(input)? {
+        instruction.unfold().for_each(|dir| rope.step(dir));
+    }
+    Ok(rope)
+}
+
+/// The knot positions after every instruction, drawn like in the puzzle description: `H` for
+/// the head, digits for the following knots and `s` for the start. All frames are cropped to the
+/// area any knot has visited.
+struct RopeAnimation {
+    frames: Vec<(MovementInstruction, Vec<Location>)>,
+    rope: Rope,
 }
 
-fn simulate_movement(
-    mut input: impl Iterator<Item = Direction>,
-) -> impl Iterator<Item = (Location, Location, Option<Direction>)> {
-    itertools::unfold(
-        (Default::default(), Default::default()),
-        move |(head, tail)| {
-            input.next().map(|ins| {
-                let next_head = ins.apply(head);
-                let (next_tail, movements) = if !next_head.touches(tail) {
-                    let dx = (next_head.0 - tail.0).clamp(-1, 1);
-                    let dy = (next_head.1 - tail.1).clamp(-1, 1);
-
-                    let dir = match (dx, dy) {
-                        (1, 0) => Direction::Right,
-                        (1, 1) => Direction::UpRight,
-                        (0, 1) => Direction::Up,
-                        (-1, 1) => Direction::UpLeft,
-                        (-1, 0) => Direction::Left,
-                        (-1, -1) => Direction::DownLeft,
-                        (0, -1) => Direction::Down,
-                        (1, -1) => Direction::DownRight,
-                        _ => panic!("Must not get here"),
-                    };
-                    (Location(tail.0 + dx, tail.1 + dy), Some(dir))
-                } else {
-                    (tail.clone(), None)
-                };
-
-                *head = next_head.clone();
-                *tail = next_tail.clone();
-                (next_head, next_tail, movements)
+impl RopeAnimation {
+    fn record(instructions: &[MovementInstruction], len: usize) -> Self {
+        let mut rope = Rope::new(len);
+        let mut frames = Vec::with_capacity(instructions.len());
+        for &instruction in instructions {
+            instruction.unfold().for_each(|dir| rope.step(dir));
+            frames.push((instruction, rope.knots().to_vec()));
+        }
+        RopeAnimation { frames, rope }
+    }
+
+    /// Top left and bottom right corner of the area visited by any knot
+    fn bounds(&self) -> (Location, Location) {
+        let all = (0..self.rope.knots().len()).flat_map(|idx| self.rope.visited(idx));
+        all.fold(
+            (Location::default(), Location::default()),
+            |(min, max), loc| {
+                (
+                    Location::new(min.x.min(loc.x), min.y.min(loc.y)),
+                    Location::new(max.x.max(loc.x), max.y.max(loc.y)),
+                )
+            },
+        )
+    }
+
+    /// Knots past the ninth one have no label of their own
+    fn knot_label(idx: usize) -> char {
+        match idx {
+            0 => 'H',
+            1..=9 => char::from_digit(idx as u32, 10).unwrap(),
+            _ => '*',
+        }
+    }
+
+    fn draw(&self, cell: impl Fn(Location) -> char) -> String {
+        let (min, max) = self.bounds();
+        (min.y..=max.y)
+            .map(|y| {
+                (min.x..=max.x)
+                    .map(|x| cell(Location::new(x, y)))
+                    .collect::<String>()
             })
-        },
-    )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Knots in front cover the ones behind them, any knot covers the start
+    fn frame(&self, idx: usize) -> String {
+        let knots = &self.frames[idx].1;
+        self.draw(|loc| match knots.iter().position(|k| *k == loc) {
+            Some(knot) => Self::knot_label(knot),
+            None if loc == Location::default() => 's',
+            None => '.',
+        })
+    }
+
+    fn tail_frame(&self) -> String {
+        let visited = self.rope.tail_visited();
+        self.draw(|loc| {
+            if loc == Location::default() {
+                's'
+            } else if visited.contains(&loc) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+    }
+}
+
+impl Display for RopeAnimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (instruction, _)) in self.frames.iter().enumerate() {
+            writeln!(f, "== {} ==\n\n{}\n", instruction, self.frame(idx))?;
+        }
+        write!(f, "== Tail ==\n\n{}", self.tail_frame())
+    }
 }
 
 fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
-    Ok(simulate_movement(
-        stream_items_from_file::<P, MovementInstruction>(input)?
-            .map(|mmi| mmi.expect("Invalid movement in input"))
-            .flat_map(|i| i.unfold()),
-    )
-    .map(|(_, tail, _)| tail)
-    .collect::<HashSet<_>>()
-    .len())
+    Ok(simulate_rope(input, 2)?.tail_visited().len())
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
-    let input_instructions = stream_items_from_file::<P, MovementInstruction>(input)?
-        .map(|mmi| mmi.expect("Invalid movement in input"))
-        .flat_map(|i| i.unfold());
-    let mut tail_visited =
-        simulate_movement((0..9).fold::<Box<dyn Iterator<Item = Direction>>, _>(
-            Box::new(input_instructions),
-            |ins, _| Box::new(simulate_movement(ins).flat_map(|(_, _, i)| i)),
-        ))
-        .map(|(h, _, _)| h)
-        .collect::<HashSet<_>>();
-
-    tail_visited.insert(Location(0, 0));
-
-    Ok(tail_visited.len())
+    Ok(simulate_rope(input, 10)?.tail_visited().len())
 }
 
 fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("rope") {
+        let len = args.get(1).map_or(Ok(10), |n| n.parse())?;
+        let rope = simulate_rope(INPUT, len)?;
+        for (idx, knot) in rope.knots().iter().enumerate() {
+            println!(
+                "Knot {} ends at {} after visiting {} locations",
+                idx,
+                knot,
+                rope.visited(idx).len()
+            );
+        }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("animate") {
+        let len = args.get(1).map_or(Ok(10), |n| n.parse())?;
+        let instructions = read_instructions(args.get(2).map_or(INPUT, String::as_str))?;
+        println!("{}", RopeAnimation::record(&instructions, len));
+        return Ok(());
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2: {}", part2(INPUT)?);
 
@@ -210,4 +278,80 @@ mod tests {
         assert_eq!(part2(&file).unwrap(), 36);
         drop(dir);
     }
+
+    #[test]
+    fn test_d09_rope() {
+        let mut rope = Rope::new(3);
+        for dir in [Direction4::Right, Direction4::Right, Direction4::Up] {
+            rope.step(dir);
+        }
+        assert_eq!(
+            rope.knots(),
+            &[
+                Location::new(2, -1),
+                Location::new(1, 0),
+                Location::new(0, 0)
+            ]
+        );
+        rope.step(Direction4::Up);
+        assert_eq!(
+            rope.knots(),
+            &[
+                Location::new(2, -2),
+                Location::new(2, -1),
+                Location::new(1, -1)
+            ]
+        );
+        assert_eq!(rope.visited(0).len(), 5);
+        assert_eq!(rope.visited(1).len(), 3);
+        assert_eq!(rope.tail_visited().len(), 2);
+
+        let (dir, file) = create_example_file("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n", None);
+        let rope = simulate_rope(&file, 10).unwrap();
+        assert_eq!(rope.knots()[0], Location::new(-11, -15));
+        assert_eq!(rope.tail_visited().len(), 36);
+        assert_eq!(rope.visited(1).len(), 88);
+        drop(dir);
+    }
+
+    #[test]
+    fn test_d09_animation() {
+        let (dir, file) = create_example_file("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n", None);
+        let animation = RopeAnimation::record(&read_instructions(&file).unwrap(), 10);
+        assert_eq!(animation.frame(0), "......\n......\n......\n......\n4321H.");
+        assert_eq!(animation.frame(1), "....H.\n....1.\n..432.\n.5....\n6.....");
+        assert_eq!(
+            animation.tail_frame(),
+            "......\n......\n......\n......\ns....."
+        );
+        assert!(animation.to_string().starts_with("== R 4 ==\n\n......\n"));
+        drop(dir);
+
+        let (dir, file) = create_example_file("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n", None);
+        let animation = RopeAnimation::record(&read_instructions(&file).unwrap(), 10);
+        let last = animation.frame(7);
+        let rows = last.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 21);
+        assert_eq!(rows[0], "H.........................");
+        assert_eq!(rows[9], "9.........................");
+        assert_eq!(rows[15], "...........s..............");
+        assert_eq!(
+            animation.tail_frame().lines().skip(9).collect::<Vec<_>>(),
+            [
+                "#.........................",
+                "#.............###.........",
+                "#............#...#........",
+                ".#..........#.....#.......",
+                "..#..........#.....#......",
+                "...#........#.......#.....",
+                "....#......s.........#....",
+                ".....#..............#.....",
+                "......#............#......",
+                ".......#..........#.......",
+                "........#........#........",
+                ".........########.........",
+            ]
+        );
+        drop(dir);
+    }
 }
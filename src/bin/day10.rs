@@ -1,16 +1,64 @@
 use advent_of_code_2022::stream_items_from_file;
 use anyhow::Result;
-use std::{num::ParseIntError, path::Path, str::FromStr};
+use lazy_static::lazy_static;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    iter,
+    num::ParseIntError,
+    path::Path,
+    str::FromStr,
+};
 use thiserror::Error;
 
 const INPUT: &str = "input/day10.txt";
 
-#[derive(Debug, Clone)]
-enum Instruction {
-    NoOp,
-    AddX(i64),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Registers {
+    x: i64,
 }
 
+impl Default for Registers {
+    fn default() -> Self {
+        Registers { x: 1 }
+    }
+}
+
+/// An opcode the CPU can execute. The registers only change once the last cycle of an
+/// operation has finished.
+trait Operation: Debug {
+    fn cycles(&self) -> usize;
+
+    fn execute(&self, registers: &mut Registers);
+}
+
+#[derive(Debug)]
+struct NoOp;
+
+impl Operation for NoOp {
+    fn cycles(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, _registers: &mut Registers) {}
+}
+
+#[derive(Debug)]
+struct AddX(i64);
+
+impl Operation for AddX {
+    fn cycles(&self) -> usize {
+        2
+    }
+
+    fn execute(&self, registers: &mut Registers) {
+        registers.x += self.0;
+    }
+}
+
+#[derive(Debug)]
+struct Instruction(Box<dyn Operation>);
+
 #[derive(Error, Debug)]
 enum InstructionParseError {
     #[error("Invalid OpCode in this line: '{0}'")]
@@ -21,51 +69,180 @@ enum InstructionParseError {
     InvalidIntegerParam(#[from] ParseIntError),
 }
 
+/// Turns a whole line starting with the opcode the parser is registered for into an operation
+type OperationParser = fn(&str) -> Result<Box<dyn Operation>, InstructionParseError>;
+
+/// The opcodes known to the parser
+struct InstructionSet {
+    parsers: HashMap<&'static str, OperationParser>,
+}
+
+impl InstructionSet {
+    fn new() -> Self {
+        InstructionSet {
+            parsers: HashMap::new(),
+        }
+    }
+
+    fn with(mut self, opcode: &'static str, parser: OperationParser) -> Self {
+        self.parsers.insert(opcode, parser);
+        self
+    }
+
+    fn standard() -> Self {
+        Self::new()
+            .with("noop", |_| Ok(Box::new(NoOp)))
+            .with("addx", |s| {
+                s.split_once(' ')
+                    .ok_or_else(|| InstructionParseError::MissingParam(s.to_string()))
+                    .and_then(|(_, arg)| Ok(Box::new(AddX(arg.parse()?)) as Box<dyn Operation>))
+            })
+    }
+
+    fn parse(&self, s: &str) -> Result<Instruction, InstructionParseError> {
+        let opcode = s.split(' ').next().unwrap_or_default();
+        let parser = self
+            .parsers
+            .get(opcode)
+            .ok_or_else(|| InstructionParseError::InvalidOpCode(s.to_string()))?;
+        Ok(Instruction(parser(s)?))
+    }
+}
+
+lazy_static! {
+    static ref STANDARD_INSTRUCTIONS: InstructionSet = InstructionSet::standard();
+}
+
 impl FromStr for Instruction {
     type Err = InstructionParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "noop" {
-            Ok(Self::NoOp)
-        } else if s.starts_with("addx ") {
-            s.split_once(' ')
-                .ok_or_else(|| InstructionParseError::MissingParam(s.to_string()))
-                .and_then(|(_, arg)| Ok(Self::AddX(arg.parse()?)))
-        } else {
-            Err(InstructionParseError::InvalidOpCode(s.to_string()))
-        }
+        STANDARD_INSTRUCTIONS.parse(s)
     }
 }
 
-impl Instruction {
-    fn get_cycles(&self) -> usize {
-        match self {
-            Instruction::NoOp => 1,
-            Instruction::AddX(_) => 2,
+/// The registers while and right after a cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cycle {
+    /// Cycles are counted from 1
+    number: usize,
+    during: Registers,
+    after: Registers,
+}
+
+/// Why `Cpu::run` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    /// The program has no more instructions
+    Halted,
+    /// The given cycle has a breakpoint and is about to start
+    Breakpoint(usize),
+}
+
+type CycleObserver<'a> = Box<dyn FnMut(usize, &Registers) + 'a>;
+
+struct Cpu<'a> {
+    registers: Registers,
+    program: Vec<Instruction>,
+    /// Index of the instruction that is currently executed
+    pc: usize,
+    /// Cycles already spent on the current instruction
+    progress: usize,
+    /// Number of finished cycles
+    cycle: usize,
+    breakpoints: HashSet<usize>,
+    /// The breakpoint `run` stopped at last, so that the next `run` does not stop there again
+    resumed_from: Option<usize>,
+    during_observers: Vec<CycleObserver<'a>>,
+    after_observers: Vec<CycleObserver<'a>>,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: Vec<Instruction>) -> Self {
+        Cpu {
+            registers: Registers::default(),
+            program,
+            pc: 0,
+            progress: 0,
+            cycle: 0,
+            breakpoints: HashSet::new(),
+            resumed_from: None,
+            during_observers: Vec::new(),
+            after_observers: Vec::new(),
         }
     }
 
-    fn run(&self, x: i64) -> (i64, usize) {
-        let cycles = self.get_cycles();
-        let new_x = match self {
-            Instruction::NoOp => x,
-            Instruction::AddX(v) => x + v,
-        };
+    fn registers(&self) -> &Registers {
+        &self.registers
+    }
 
-        (new_x, cycles)
+    /// Call `observer` with the cycle number and the registers while each cycle runs
+    fn on_during(&mut self, observer: impl FnMut(usize, &Registers) + 'a) {
+        self.during_observers.push(Box::new(observer));
     }
-}
 
-fn run_program(mut input: impl Iterator<Item = Instruction>) -> impl Iterator<Item = i64> {
-    itertools::unfold(1, move |x| {
-        input.next().map(|instruction| {
-            let (new_x, cycles) = instruction.run(*x);
-            let out = vec![*x; cycles];
-            *x = new_x;
-            out
+    /// Call `observer` with the cycle number and the registers after each cycle has finished
+    fn on_after(&mut self, observer: impl FnMut(usize, &Registers) + 'a) {
+        self.after_observers.push(Box::new(observer));
+    }
+
+    fn add_breakpoint(&mut self, cycle: usize) {
+        self.breakpoints.insert(cycle);
+    }
+
+    /// Run a single cycle, `None` once the program has finished
+    fn step(&mut self) -> Option<Cycle> {
+        let instruction = self.program.get(self.pc)?;
+        self.cycle += 1;
+        let during = self.registers;
+        for observer in &mut self.during_observers {
+            observer(self.cycle, &during);
+        }
+
+        self.progress += 1;
+        if self.progress >= instruction.0.cycles() {
+            instruction.0.execute(&mut self.registers);
+            self.pc += 1;
+            self.progress = 0;
+        }
+        for observer in &mut self.after_observers {
+            observer(self.cycle, &self.registers);
+        }
+
+        Some(Cycle {
+            number: self.cycle,
+            during,
+            after: self.registers,
         })
-    })
-    .flatten()
+    }
+
+    /// Run until the program halts or the next cycle has a breakpoint. Calling this again after
+    /// stopping at a breakpoint continues past it.
+    fn run(&mut self) -> Stop {
+        loop {
+            let next = self.cycle + 1;
+            if self.pc < self.program.len()
+                && self.breakpoints.contains(&next)
+                && self.resumed_from != Some(next)
+            {
+                self.resumed_from = Some(next);
+                return Stop::Breakpoint(next);
+            }
+            if self.step().is_none() {
+                return Stop::Halted;
+            }
+        }
+    }
+}
+
+fn read_program<P: AsRef<Path>>(input: P) -> Result<Vec<Instruction>> {
+    Ok(stream_items_from_file::<P, Instruction>(input)?.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// The value of the X register during every cycle
+fn run_program(input: impl Iterator<Item = Instruction>) -> impl Iterator<Item = i64> {
+    let mut cpu = Cpu::new(input.collect());
+    iter::from_fn(move || cpu.step().map(|cycle| cycle.during.x))
 }
 
 fn draw_crt(register_states: impl Iterator<Item = i64>) -> String {
@@ -88,27 +265,56 @@ fn draw_crt(register_states: impl Iterator<Item = i64>) -> String {
 }
 
 fn part1<P: AsRef<Path>>(input: P) -> Result<i64> {
-    Ok(run_program(
-        stream_items_from_file::<P, Instruction>(input)?
-            .map(|mi| mi.expect("Unparseable instruction")),
-    )
-    .enumerate()
-    .filter(|(step, _)| {
-        let rstep = step + 1;
-        rstep == 20 || (rstep >= 60 && ((rstep - 20) % 40 == 0))
-    })
-    .map(|(step, x)| ((step + 1) as i64) * x)
-    .sum())
+    let mut signal_strength = 0;
+    let mut cpu = Cpu::new(read_program(input)?);
+    cpu.on_during(|cycle, registers| {
+        if cycle >= 20 && (cycle - 20) % 40 == 0 {
+            signal_strength += cycle as i64 * registers.x;
+        }
+    });
+    cpu.run();
+    drop(cpu);
+    Ok(signal_strength)
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<String> {
-    Ok(draw_crt(run_program(
-        stream_items_from_file::<P, Instruction>(input)?
-            .map(|mi| mi.expect("Unparseable instruction")),
-    )))
+    Ok(draw_crt(run_program(read_program(input)?.into_iter())))
 }
 
 fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("trace") => {
+            let mut cpu = Cpu::new(read_program(INPUT)?);
+            while let Some(cycle) = cpu.step() {
+                println!(
+                    "Cycle {:>3}: X during {:>3}, after {:>3}",
+                    cycle.number, cycle.during.x, cycle.after.x
+                );
+            }
+            return Ok(());
+        }
+        Some("break") => {
+            let mut cpu = Cpu::new(read_program(INPUT)?);
+            for cycle in &args[1..] {
+                cpu.add_breakpoint(cycle.parse()?);
+            }
+            let mut last = *cpu.registers();
+            cpu.on_after(move |cycle, registers| {
+                if *registers != last {
+                    println!("After cycle {}: X = {}", cycle, registers.x);
+                    last = *registers;
+                }
+            });
+            while let Stop::Breakpoint(cycle) = cpu.run() {
+                println!("Before cycle {}: X = {}", cycle, cpu.registers().x);
+            }
+            println!("Halted with X = {}", cpu.registers().x);
+            return Ok(());
+        }
+        _ => {}
+    }
+
     println!("Answer for part 1: {}", part1(INPUT)?);
     println!("Answer for part 2:\n{}", part2(INPUT)?);
 
@@ -309,4 +515,84 @@ mod tests {
         assert_eq!(out_states, vec![1, 1, 1, 4, 4]);
         drop(dir);
     }
+
+    #[derive(Debug)]
+    struct MulX(i64);
+
+    impl Operation for MulX {
+        fn cycles(&self) -> usize {
+            3
+        }
+
+        fn execute(&self, registers: &mut Registers) {
+            registers.x *= self.0;
+        }
+    }
+
+    #[test]
+    fn test_d10_cpu() {
+        let instructions = InstructionSet::standard().with("mulx", |s| {
+            let (_, arg) = s
+                .split_once(' ')
+                .ok_or_else(|| InstructionParseError::MissingParam(s.to_string()))?;
+            Ok(Box::new(MulX(arg.parse()?)))
+        });
+        let program = ["addx 2", "mulx 5", "noop"]
+            .iter()
+            .map(|l| instructions.parse(l))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(matches!(
+            instructions.parse("jmp 3"),
+            Err(InstructionParseError::InvalidOpCode(_))
+        ));
+        assert!("mulx 5".parse::<Instruction>().is_err());
+
+        let mut during = Vec::new();
+        let mut after = Vec::new();
+        let mut cpu = Cpu::new(program);
+        cpu.on_during(|cycle, r| during.push((cycle, r.x)));
+        cpu.on_after(|_, r| after.push(r.x));
+        cpu.add_breakpoint(3);
+        cpu.add_breakpoint(6);
+        assert_eq!(cpu.run(), Stop::Breakpoint(3));
+        assert_eq!(cpu.registers().x, 3);
+        assert_eq!(
+            cpu.step(),
+            Some(Cycle {
+                number: 3,
+                during: Registers { x: 3 },
+                after: Registers { x: 3 }
+            })
+        );
+        assert_eq!(cpu.run(), Stop::Breakpoint(6));
+        assert_eq!(cpu.registers().x, 15);
+        assert_eq!(cpu.run(), Stop::Halted);
+        assert_eq!(cpu.step(), None);
+        drop(cpu);
+
+        assert_eq!(
+            during,
+            vec![(1, 1), (2, 1), (3, 3), (4, 3), (5, 3), (6, 15)]
+        );
+        assert_eq!(after, vec![1, 3, 3, 3, 15, 15]);
+    }
+
+    #[test]
+    fn test_d10_breakpoint_on_first_cycle() {
+        let program = ["noop", "addx 3", "noop"]
+            .iter()
+            .map(|l| l.parse::<Instruction>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut cpu = Cpu::new(program);
+        cpu.add_breakpoint(1);
+        cpu.add_breakpoint(4);
+        assert_eq!(cpu.run(), Stop::Breakpoint(1));
+        assert_eq!(cpu.registers().x, 1);
+        assert_eq!(cpu.run(), Stop::Breakpoint(4));
+        assert_eq!(cpu.registers().x, 4);
+        assert_eq!(cpu.run(), Stop::Halted);
+        assert_eq!(cpu.run(), Stop::Halted);
+    }
 }
@@ -1,4 +1,4 @@
-use advent_of_code_2022::stream_file_blocks;
+use advent_of_code_2022::{stream_file_blocks, top_k::top_k};
 use anyhow::Result;
 use std::{num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
@@ -16,7 +16,7 @@ impl ThrowTest {
     fn run(&self, item: usize) -> Throw {
         Throw {
             item,
-            to: if item % self.divisible_by == 0 {
+            to: if item.is_multiple_of(self.divisible_by) {
                 self.if_true
             } else {
                 self.if_false
@@ -205,7 +205,7 @@ struct Throw {
 }
 
 impl Throw {
-    fn execute(self, monkeys: &mut Vec<Monkey>) {
+    fn execute(self, monkeys: &mut [Monkey]) {
         monkeys[self.to].items.push(self.item);
     }
 }
@@ -226,15 +226,7 @@ fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
         }
     }
 
-    let (most_idx, &most_val) = monkey_throw_counts
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, v)| *v)
-        .unwrap();
-    monkey_throw_counts.swap_remove(most_idx);
-    let second_most_val = monkey_throw_counts.iter().max().unwrap();
-
-    Ok(most_val * second_most_val)
+    Ok(top_k(monkey_throw_counts, 2).into_iter().product())
 }
 
 fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
@@ -245,7 +237,7 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
     let lcm = monkeys
         .iter()
         .map(|monkey| monkey.throw_test.divisible_by)
-        .reduce(|x, y| num::integer::lcm(x, y))
+        .reduce(num::integer::lcm)
         .unwrap();
 
     let mut monkey_throw_counts = vec![0; monkeys.len()];
@@ -260,15 +252,7 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
         }
     }
 
-    let (most_idx, &most_val) = monkey_throw_counts
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, v)| *v)
-        .unwrap();
-    monkey_throw_counts.swap_remove(most_idx);
-    let second_most_val = monkey_throw_counts.iter().max().unwrap();
-
-    Ok(most_val * second_most_val)
+    Ok(top_k(monkey_throw_counts, 2).into_iter().product())
 }
 
 fn main() -> Result<()> {
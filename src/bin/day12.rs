@@ -1,20 +1,20 @@
-use advent_of_code_2022::{field2d::Field2D, stream_items_from_file};
+use advent_of_code_2022::{field2d::Field2D, geometry::Point2, stream_items_from_file};
 use anyhow::Result;
-use std::{collections::BinaryHeap,  path::Path };
+use std::{collections::BinaryHeap, path::Path};
 
 const INPUT: &str = "input/day12.txt";
 
 #[derive(Debug, Clone)]
 struct Heightmap {
-    start: (usize, usize),
-    goal: (usize, usize),
+    start: Point2<usize>,
+    goal: Point2<usize>,
     map: Field2D<usize>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 struct State {
     cost: usize,
-    position: (usize, usize),
+    position: Point2<usize>,
 }
 
 impl Ord for State {
@@ -44,10 +44,10 @@ impl Heightmap {
                 .enumerate()
                 .map(|(i, h)| {
                     if h == 'S' {
-                        start = Some((i, line_idx));
+                        start = Some(Point2::new(i, line_idx));
                         1
                     } else if h == 'E' {
-                        goal = Some((i, line_idx));
+                        goal = Some(Point2::new(i, line_idx));
                         26
                     } else {
                         (h as u8 - b'a') as usize
@@ -89,7 +89,8 @@ impl Heightmap {
 
             for neighbor in self
                 .map
-                .neighbors(position.0, position.1)
+                .neighbors(position.x, position.y)
+                .map(Point2::from)
                 .filter(|neighbor| self.map[*neighbor] <= self.map[position] + 1)
             {
                 let next = State {
@@ -126,7 +127,8 @@ impl Heightmap {
 
             for neighbor in self
                 .map
-                .neighbors(position.0, position.1)
+                .neighbors(position.x, position.y)
+                .map(Point2::from)
                 .filter(|neighbor| self.map[*neighbor] + 1 >= self.map[position])
             {
                 let next = State {
@@ -157,7 +159,8 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
         .iter_with_position()
         .filter(|(pos, _)| map.map[*pos] == 0)
         .min_by_key(|(_, dist)| *dist)
-        .unwrap().1)
+        .unwrap()
+        .1)
 }
 
 fn main() -> Result<()> {
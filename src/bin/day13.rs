@@ -34,16 +34,14 @@ impl FromStr for Packet {
             return Err(PacketParseError::InvalidStart(s.to_string()));
         } else {
             for c in input {
-                if c == ']' || c == ',' || c == '[' {
-                    if !current_number.is_empty() {
-                        parse_stack
-                            .last_mut()
-                            .unwrap()
-                            .push(Packet::Value(current_number.parse().unwrap()));
-                        current_number.clear();
-                    }
+                if (c == ']' || c == ',' || c == '[') && !current_number.is_empty() {
+                    parse_stack
+                        .last_mut()
+                        .unwrap()
+                        .push(Packet::Value(current_number.parse().unwrap()));
+                    current_number.clear();
                 }
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     current_number.push(c);
                 } else if c == ']' {
                     let packet = parse_stack.pop().unwrap();
@@ -65,7 +63,7 @@ impl FromStr for Packet {
 }
 
 impl Packet {
-    fn as_list(self) -> Self {
+    fn into_list(self) -> Self {
         match self {
             Packet::Value(v) => Packet::List(vec![Packet::Value(v)]),
             Packet::List(_) => self,
@@ -100,7 +98,7 @@ impl Ord for Packet {
             // "If exactly one value is an integer, convert the integer to a list which contains that integer
             // as its only value, then retry the comparison."
             // This is pretty inefficient due to the clone calls, but it works.
-            self.clone().as_list().cmp(&other.clone().as_list())
+            self.clone().into_list().cmp(&other.clone().into_list())
         } else if self.is_list() && other.is_list() {
             // If both values are lists, do a normal list comparison
             self.get_children()
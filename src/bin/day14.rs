@@ -1,11 +1,12 @@
-use advent_of_code_2022::stream_items_from_file;
+use advent_of_code_2022::{geometry::Point2, stream_items_from_file};
 use anyhow::Result;
-use std::{cmp, collections::HashSet, num::ParseIntError, path::Path, str::FromStr};
+use std::{collections::HashSet, num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
 
 const INPUT: &str = "input/day14.txt";
+const SAND_SOURCE: Coord = Point2::new(500, 0);
 
-type Coord = (isize, isize);
+type Coord = Point2<isize>;
 
 #[derive(Debug, Clone)]
 struct Line(Vec<Coord>);
@@ -28,7 +29,7 @@ impl FromStr for Line {
                     p.trim()
                         .split_once(',')
                         .ok_or(LineParseError::InvalidPair)
-                        .and_then(|(x, y)| Ok((x.parse()?, y.parse()?)))
+                        .and_then(|(x, y)| Ok(Point2::new(x.parse()?, y.parse()?)))
                 })
                 .collect::<Result<Vec<_>, _>>()?,
         ))
@@ -41,33 +42,26 @@ impl Line {
             .iter()
             .zip(self.0[1..].iter())
             .flat_map(move |(segment_start, segment_end)| {
-                if segment_start.1 == segment_end.1 {
-                    let start = cmp::min(segment_start.0, segment_end.0);
-                    let end = cmp::max(segment_start.0, segment_end.0);
-                    (start..=end)
-                        .map(|x| (x, segment_start.1))
-                        .collect::<Vec<_>>()
-                } else {
-                    let start = cmp::min(segment_start.1, segment_end.1);
-                    let end = cmp::max(segment_start.1, segment_end.1);
-                    (start..=end)
-                        .map(|y| (segment_start.0, y))
-                        .collect::<Vec<_>>()
-                }
+                let step = (*segment_end - *segment_start).signum();
+                (0..=segment_start.chebyshev_distance(segment_end))
+                    .map(move |i| *segment_start + step * i)
             })
     }
 }
 
+/// Sand tries to fall straight down first, then diagonally to the left and then to the right
+const FALL_DIRECTIONS: [Coord; 3] = [Point2::new(0, 1), Point2::new(-1, 1), Point2::new(1, 1)];
+
 fn drop_sand_bottomless(environment: &HashSet<Coord>, start: Coord) -> Option<Coord> {
-    let lowest_point = *environment.iter().map(|(_, y)| y).max().unwrap();
-    let mut sand_pos = start.clone();
-    while sand_pos.1 < lowest_point {
-        if !environment.contains(&(sand_pos.0, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0, sand_pos.1 + 1);
-        } else if !environment.contains(&(sand_pos.0 - 1, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0 - 1, sand_pos.1 + 1);
-        } else if !environment.contains(&(sand_pos.0 + 1, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0 + 1, sand_pos.1 + 1);
+    let lowest_point = environment.iter().map(|p| p.y).max().unwrap();
+    let mut sand_pos = start;
+    while sand_pos.y < lowest_point {
+        if let Some(next) = FALL_DIRECTIONS
+            .iter()
+            .map(|delta| sand_pos + *delta)
+            .find(|candidate| !environment.contains(candidate))
+        {
+            sand_pos = next;
         } else {
             return Some(sand_pos);
         }
@@ -77,14 +71,14 @@ fn drop_sand_bottomless(environment: &HashSet<Coord>, start: Coord) -> Option<Co
 }
 
 fn drop_sand_with_floor(environment: &HashSet<Coord>, start: Coord, floor: isize) -> Coord {
-    let mut sand_pos = start.clone();
-    while sand_pos.1 < floor - 1 {
-        if !environment.contains(&(sand_pos.0, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0, sand_pos.1 + 1);
-        } else if !environment.contains(&(sand_pos.0 - 1, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0 - 1, sand_pos.1 + 1);
-        } else if !environment.contains(&(sand_pos.0 + 1, sand_pos.1 + 1)) {
-            sand_pos = (sand_pos.0 + 1, sand_pos.1 + 1);
+    let mut sand_pos = start;
+    while sand_pos.y < floor - 1 {
+        if let Some(next) = FALL_DIRECTIONS
+            .iter()
+            .map(|delta| sand_pos + *delta)
+            .find(|candidate| !environment.contains(candidate))
+        {
+            sand_pos = next;
         } else {
             return sand_pos;
         }
@@ -99,7 +93,7 @@ fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
         .collect::<HashSet<_>>();
     let mut dropped = 0;
     loop {
-        match drop_sand_bottomless(&rocks, (500, 0)) {
+        match drop_sand_bottomless(&rocks, SAND_SOURCE) {
             Some(p) => {
                 rocks.insert(p);
                 dropped += 1;
@@ -115,11 +109,11 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
         .flat_map(|l| l.get_points().collect::<Vec<_>>())
         .collect::<HashSet<_>>();
     let mut dropped = 0;
-    let lowest_rock = *rocks.iter().map(|(_, y)| y).max().unwrap();
+    let lowest_rock = rocks.iter().map(|p| p.y).max().unwrap();
     loop {
-        let pos = drop_sand_with_floor(&rocks, (500, 0), lowest_rock + 2);
+        let pos = drop_sand_with_floor(&rocks, SAND_SOURCE, lowest_rock + 2);
         dropped += 1;
-        if pos == (500, 0) {
+        if pos == SAND_SOURCE {
             return Ok(dropped);
         } else {
             rocks.insert(pos);
@@ -1,15 +1,15 @@
-use advent_of_code_2022::stream_items_from_file;
+use advent_of_code_2022::{geometry::Point2, interval_set::IntervalSet, stream_items_from_file};
 use anyhow::anyhow;
 use anyhow::Result;
 use itertools::{chain, Itertools};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::{collections::HashSet, path::Path, str::FromStr};
+use std::{collections::HashSet, ops::Range, path::Path, str::FromStr};
 use thiserror::Error;
 
 const INPUT: &str = "input/day15.txt";
 
-type Coordinate = (isize, isize);
+type Coordinate = Point2<isize>;
 
 struct Sensor {
     location: Coordinate,
@@ -33,102 +33,70 @@ impl FromStr for Sensor {
         let (location, closest_beacon) = NUMBER_REGEX
             .find_iter(s)
             .map(|found| found.as_str().parse::<isize>().unwrap())
-            .tuples()
+            .tuples::<(_, _)>()
             .tuples()
             .next()
             .ok_or(SensorParseError::InvalidDescriptor)?;
         Ok(Sensor {
-            location,
-            closest_beacon,
+            location: location.into(),
+            closest_beacon: closest_beacon.into(),
         })
     }
 }
 
-fn manhattan_distance(a: &Coordinate, b: &Coordinate) -> usize {
-    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
-}
-
 impl Sensor {
     fn covers(&self, coord: &Coordinate) -> bool {
-        let range = manhattan_distance(&self.location, &self.closest_beacon);
-        manhattan_distance(&self.location, coord) <= range
+        self.location.manhattan_distance(coord) <= self.range()
     }
 
-    fn get_min_x(&self) -> isize {
-        let range = manhattan_distance(&self.location, &self.closest_beacon);
-        self.location.0 - (range as isize)
+    /// The x coordinates on `row` that are within range of this sensor
+    fn covered_on_row(&self, row: isize) -> Range<isize> {
+        let remaining = self.range() - self.location.y.abs_diff(row) as isize;
+        (self.location.x - remaining)..(self.location.x + remaining + 1)
     }
 
-    fn get_max_x(&self) -> isize {
-        let range = manhattan_distance(&self.location, &self.closest_beacon);
-        self.location.0 + (range as isize)
-    }
-
-    fn range(&self) -> usize {
-        manhattan_distance(&self.location, &self.closest_beacon)
+    fn range(&self) -> isize {
+        self.location.manhattan_distance(&self.closest_beacon)
     }
 
     /// Get a ring of candidates around the range of this sensor.
     fn get_uncovered_candidates(&self) -> impl Iterator<Item = Coordinate> + '_ {
         chain![
-            (0..self.range() as isize + 1).map(|i| {
-                (
-                    self.location.0 + self.range() as isize + 1 - i,
-                    self.location.1 + i,
-                )
-            }),
-            (0..self.range() as isize).map(|i| {
-                (
-                    self.location.0 + i,
-                    self.location.1 + self.range() as isize + 1 - i,
-                )
-            }),
-            (0..self.range() as isize).map(|i| {
-                (
-                    self.location.0 - self.range() as isize - 1 + i,
-                    self.location.1 + i,
-                )
-            }),
-            (0..self.range() as isize).map(|i| {
-                (
-                    self.location.0 + i,
-                    self.location.1 - self.range() as isize - 1 + i,
-                )
-            }),
+            (0..self.range() + 1).map(|i| self.location + Point2::new(self.range() + 1 - i, i)),
+            (0..self.range()).map(|i| self.location + Point2::new(i, self.range() + 1 - i)),
+            (0..self.range()).map(|i| self.location + Point2::new(-self.range() - 1 + i, i)),
+            (0..self.range()).map(|i| self.location + Point2::new(i, -self.range() - 1 + i)),
         ]
     }
 }
 
 fn part1<P: AsRef<Path>>(input: P, row: isize) -> Result<usize> {
-    // This is a inefficient solution, lots of hashmap lookups and stuff...
     let sensors = stream_items_from_file::<P, Sensor>(input)?.collect::<Result<Vec<_>, _>>()?;
-    let min_x = sensors.iter().map(|s| s.get_min_x()).min().unwrap();
-    let max_x = sensors.iter().map(|s| s.get_max_x()).max().unwrap();
-    let beacons = sensors
+    let covered = sensors
+        .iter()
+        .map(|s| s.covered_on_row(row))
+        .collect::<IntervalSet<_>>();
+    // A beacon can obviously be at the positions of the known beacons, so don't count those
+    let beacons_on_row = sensors
         .iter()
         .map(|s| s.closest_beacon)
+        .filter(|b| b.y == row && covered.contains(&b.x))
         .collect::<HashSet<_>>();
 
-    Ok((min_x..=max_x)
-        .filter(|x| !beacons.contains(&(*x, row)) && sensors.iter().any(|s| s.covers(&(*x, row))))
-        .count())
+    Ok(covered.len() as usize - beacons_on_row.len())
 }
 
 fn part2<P: AsRef<Path>>(input: P, xlim: isize, ylim: isize) -> Result<usize> {
     let sensors = stream_items_from_file::<P, Sensor>(input)?.collect::<Result<Vec<_>, _>>()?;
     for sensor in &sensors {
-        if let Some(coordinates) = sensor
-            .get_uncovered_candidates()
-            .filter(|cand| {
-                cand.0 >= 0
-                    && cand.1 >= 0
-                    && cand.0 <= xlim
-                    && cand.1 <= ylim
-                    && !sensors.iter().any(|s| s.covers(&cand))
-            })
-            .next()
-        {
-            return Ok((coordinates.0 as usize * 4000000) + coordinates.1 as usize);
+        if let Some(coordinates) = sensor.get_uncovered_candidates().find(|cand| {
+            cand.x >= 0
+                && cand.y >= 0
+                && cand.x <= xlim
+                && cand.y <= ylim
+                && !sensors.iter().any(|s| s.covers(cand))
+        }) {
+            return Ok((coordinates.x as usize * 4000000) + coordinates.y as usize);
         }
     }
 
@@ -1,4 +1,4 @@
-use advent_of_code_2022::stream_items_from_file;
+use advent_of_code_2022::{geometry::Point2, stream_items_from_file};
 use anyhow::anyhow;
 use anyhow::Result;
 use std::collections::HashMap;
@@ -6,10 +6,11 @@ use std::{collections::HashSet, path::Path, str::FromStr};
 
 const INPUT: &str = "input/day17.txt";
 
-type Coordinate = (usize, usize);
+type Coordinate = Point2<usize>;
 
+/// Rock shapes are stored as plain offset tuples to keep the table below readable
 #[derive(Debug, Clone)]
-struct Rock<'a>(&'a [Coordinate], usize);
+struct Rock<'a>(&'a [(usize, usize)], usize);
 
 const ROCK_TYPES: [Rock<'static>; 5] = [
     Rock(&[(0, 0), (1, 0), (2, 0), (3, 0)], 0),
@@ -27,12 +28,12 @@ fn get_rock_types_iteration() -> impl Iterator<Item = &'static Rock<'static>> {
 
 impl<'a> Rock<'a> {
     fn iterate_rock_coords(&self) -> impl Iterator<Item = Coordinate> + 'a {
-        self.0.iter().cloned()
+        self.0.iter().copied().map(Point2::from)
     }
 
     fn check_collision(&self, other_rocks: &HashSet<Coordinate>, offset: Coordinate) -> bool {
         self.iterate_rock_coords()
-            .map(|c| (c.0 + offset.0, c.1 + offset.1))
+            .map(|c| c + offset)
             .any(|c| other_rocks.contains(&c))
     }
 }
@@ -78,7 +79,8 @@ fn drop_rock(
     rock: &Rock,
     jets: &mut impl Iterator<Item = JetDirection>,
 ) {
-    let mut rock_position = (2, cave_state.iter().map(|c| c.1 + 1).max().unwrap_or(0) + 3);
+    let mut rock_position =
+        Point2::new(2, cave_state.iter().map(|c| c.y + 1).max().unwrap_or(0) + 3);
 
     let rock_width = rock.0.iter().map(|c| c.0).max().unwrap() + 1;
 
@@ -86,36 +88,34 @@ fn drop_rock(
         // 1.: Hot jets push the rock
         match jets.next().unwrap() {
             JetDirection::Left => {
-                if rock_position.0 > 0
-                    && !rock.check_collision(cave_state, (rock_position.0 - 1, rock_position.1))
+                if rock_position.x > 0
+                    && !rock.check_collision(cave_state, rock_position - Point2::new(1, 0))
                 {
-                    rock_position.0 -= 1;
-                } else {
+                    rock_position.x -= 1;
                 }
             }
             JetDirection::Right => {
-                if rock_position.0 + rock_width < 7
-                    && !rock.check_collision(cave_state, (rock_position.0 + 1, rock_position.1))
+                if rock_position.x + rock_width < 7
+                    && !rock.check_collision(cave_state, rock_position + Point2::new(1, 0))
                 {
-                    rock_position.0 += 1;
-                } else {
+                    rock_position.x += 1;
                 }
             }
         }
 
         // 2.: Rock falls 1 block
-        if rock_position.1 == 0
-            || rock.check_collision(cave_state, (rock_position.0, rock_position.1 - 1))
+        if rock_position.y == 0
+            || rock.check_collision(cave_state, rock_position - Point2::new(0, 1))
         {
             // We hit something, stop here
             rock.iterate_rock_coords()
-                .map(|c| (rock_position.0 + c.0, rock_position.1 + c.1))
+                .map(|c| rock_position + c)
                 .for_each(|p| {
                     cave_state.insert(p);
                 });
             break;
         } else {
-            rock_position.1 -= 1;
+            rock_position.y -= 1;
         }
     }
 }
@@ -131,7 +131,7 @@ fn part1<P: AsRef<Path>>(input: P) -> Result<usize> {
         drop_rock(&mut cave_state, rock, &mut jet_pattern);
     });
 
-    let height = cave_state.iter().map(|c| c.1).max().unwrap() + 1;
+    let height = cave_state.iter().map(|c| c.y).max().unwrap() + 1;
 
     Ok(height)
 }
@@ -156,14 +156,14 @@ impl Fingerprint {
         jet: usize,
         cave_state: &HashSet<Coordinate>,
     ) -> Self {
-        let max_y = cave_state.iter().map(|c| c.1).max().unwrap();
+        let max_y = cave_state.iter().map(|c| c.y).max().unwrap();
         let mut last_rows = TopRockFingerprint::default();
 
         cave_state
             .iter()
-            .filter(|c| c.1 + FINGERPRINT_LENGTH > max_y)
+            .filter(|c| c.y + FINGERPRINT_LENGTH > max_y)
             .for_each(|c| {
-                last_rows[max_y - c.1][c.0] = true;
+                last_rows[max_y - c.y][c.x] = true;
             });
 
         Fingerprint {
@@ -203,9 +203,7 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
         if fallen_rocks > 20 {
             let fingerprint = Fingerprint::build(fallen_rocks, rock, *jet_idx, &cave_state);
             let entry = fingerprint_store.entry((rock.1, *jet_idx)).or_default();
-            if let Some(matching_fingerprint) =
-                entry.iter().filter(|f| f.matches(&fingerprint)).next()
-            {
+            if let Some(matching_fingerprint) = entry.iter().find(|f| f.matches(&fingerprint)) {
                 println!(
                     "Loop identified (from rock dropped at {} to rock dropped at {})",
                     matching_fingerprint.n_rocks, fallen_rocks
@@ -249,7 +247,7 @@ fn part2<P: AsRef<Path>>(input: P) -> Result<usize> {
             );
         });
 
-    let height = cave_state.iter().map(|c| c.1).max().unwrap() + 1 + loop_height;
+    let height = cave_state.iter().map(|c| c.y).max().unwrap() + 1 + loop_height;
 
     Ok(height)
 }
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::geometry::Vector2;
+
+// All deltas follow the `Field2D` convention: x grows to the right and y grows downwards.
+
+#[derive(Error, Debug)]
+pub enum DirectionParseError {
+    #[error("Invalid Direction '{0}'")]
+    InvalidDirection(String),
+}
+
+/// The four orthogonal headings, in clockwise order
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction4 {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+/// The eight compass headings, in clockwise order
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction4 {
+    pub const ALL: [Direction4; 4] = [
+        Direction4::Up,
+        Direction4::Right,
+        Direction4::Down,
+        Direction4::Left,
+    ];
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+
+    /// Rotate by `steps` quarter turns, positive values rotate clockwise
+    pub fn rotate(self, steps: isize) -> Self {
+        Self::ALL[(self as isize + steps).rem_euclid(4) as usize]
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(-1)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(1)
+    }
+
+    pub fn opposite(self) -> Self {
+        self.rotate(2)
+    }
+
+    pub fn delta(self) -> Vector2<isize> {
+        Direction8::from(self).delta()
+    }
+
+    pub fn from_delta(delta: Vector2<isize>) -> Option<Self> {
+        Self::all().find(|d| d.delta() == delta)
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Direction4::Left | Direction4::Right)
+    }
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up,
+        Direction8::UpRight,
+        Direction8::Right,
+        Direction8::DownRight,
+        Direction8::Down,
+        Direction8::DownLeft,
+        Direction8::Left,
+        Direction8::UpLeft,
+    ];
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+
+    /// Rotate by `steps` eighth turns, positive values rotate clockwise
+    pub fn rotate(self, steps: isize) -> Self {
+        Self::ALL[(self as isize + steps).rem_euclid(8) as usize]
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(-2)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(2)
+    }
+
+    pub fn opposite(self) -> Self {
+        self.rotate(4)
+    }
+
+    pub fn delta(self) -> Vector2<isize> {
+        match self {
+            Direction8::Up => Vector2::new(0, -1),
+            Direction8::UpRight => Vector2::new(1, -1),
+            Direction8::Right => Vector2::new(1, 0),
+            Direction8::DownRight => Vector2::new(1, 1),
+            Direction8::Down => Vector2::new(0, 1),
+            Direction8::DownLeft => Vector2::new(-1, 1),
+            Direction8::Left => Vector2::new(-1, 0),
+            Direction8::UpLeft => Vector2::new(-1, -1),
+        }
+    }
+
+    /// Find the heading of a delta, which needs to be a unit step
+    pub fn from_delta(delta: Vector2<isize>) -> Option<Self> {
+        Self::all().find(|d| d.delta() == delta)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self as usize % 2 == 1
+    }
+}
+
+impl From<Direction4> for Direction8 {
+    fn from(value: Direction4) -> Self {
+        match value {
+            Direction4::Up => Direction8::Up,
+            Direction4::Right => Direction8::Right,
+            Direction4::Down => Direction8::Down,
+            Direction4::Left => Direction8::Left,
+        }
+    }
+}
+
+impl TryFrom<Direction8> for Direction4 {
+    type Error = Direction8;
+
+    fn try_from(value: Direction8) -> Result<Self, Self::Error> {
+        match value {
+            Direction8::Up => Ok(Direction4::Up),
+            Direction8::Right => Ok(Direction4::Right),
+            Direction8::Down => Ok(Direction4::Down),
+            Direction8::Left => Ok(Direction4::Left),
+            diagonal => Err(diagonal),
+        }
+    }
+}
+
+impl FromStr for Direction4 {
+    type Err = DirectionParseError;
+
+    /// Accepts `U/D/L/R`, compass letters `N/S/E/W` and arrows `^v<>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "U" | "N" | "^" => Ok(Direction4::Up),
+            "R" | "E" | ">" => Ok(Direction4::Right),
+            "D" | "S" | "v" => Ok(Direction4::Down),
+            "L" | "W" | "<" => Ok(Direction4::Left),
+            _ => Err(DirectionParseError::InvalidDirection(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Direction8 {
+    type Err = DirectionParseError;
+
+    /// Accepts everything `Direction4` does plus combined compass headings like `NE` or `UR`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NE" | "UR" => Ok(Direction8::UpRight),
+            "SE" | "DR" => Ok(Direction8::DownRight),
+            "SW" | "DL" => Ok(Direction8::DownLeft),
+            "NW" | "UL" => Ok(Direction8::UpLeft),
+            _ => s.parse::<Direction4>().map(Direction8::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        assert_eq!(Direction4::Up.turn_left(), Direction4::Left);
+        assert_eq!(Direction4::Left.turn_right(), Direction4::Up);
+        assert_eq!(Direction4::Right.opposite(), Direction4::Left);
+        assert_eq!(Direction8::UpLeft.turn_right(), Direction8::UpRight);
+        assert_eq!(Direction8::DownRight.opposite(), Direction8::UpLeft);
+        assert_eq!(Direction8::Up.rotate(-1), Direction8::UpLeft);
+        assert!(Direction8::all().all(|d| d.delta() == -d.opposite().delta()));
+    }
+
+    #[test]
+    fn test_parsing_and_deltas() {
+        for (symbols, expected) in [("U N ^", Direction4::Up), ("L W <", Direction4::Left)] {
+            for symbol in symbols.split(' ') {
+                assert_eq!(symbol.parse::<Direction4>().unwrap(), expected);
+            }
+        }
+        assert!("X".parse::<Direction4>().is_err());
+        assert_eq!("SW".parse::<Direction8>().unwrap(), Direction8::DownLeft);
+        assert_eq!(
+            Direction8::from_delta(Vector2::new(1, -1)),
+            Some(Direction8::UpRight)
+        );
+        assert_eq!(Direction4::from_delta(Vector2::new(1, -1)), None);
+        assert_eq!(Direction4::all().count(), 4);
+    }
+}
@@ -0,0 +1,202 @@
+/// The state of a `DistinctWindow` after consuming one more byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    /// Number of bytes consumed so far, i.e. the offset right after the window
+    pub end: usize,
+    /// Number of bytes in the window, only smaller than the window size at the start of a stream
+    pub len: usize,
+    /// Number of different byte values in the window
+    pub distinct: usize,
+}
+
+impl Window {
+    pub fn is_all_distinct(&self) -> bool {
+        self.distinct == self.len
+    }
+}
+
+/// Counts how many different bytes are among the last `size` bytes pushed into it.
+/// Occurrences are counted per byte value, so every push only updates the entering and the
+/// leaving byte, no matter how large the window is.
+#[derive(Debug, Clone)]
+pub struct SlidingDistinct {
+    buffer: Vec<u8>,
+    size: usize,
+    consumed: usize,
+    counts: [usize; 256],
+    distinct: usize,
+}
+
+impl SlidingDistinct {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Window size needs to be at least 1");
+        SlidingDistinct {
+            buffer: vec![0; size],
+            size,
+            consumed: 0,
+            counts: [0; 256],
+            distinct: 0,
+        }
+    }
+
+    /// Add a byte to the window, evicting the oldest one once the window is full.
+    /// The `end` of the returned window counts the bytes pushed since the last `clear`.
+    pub fn push(&mut self, byte: u8) -> Window {
+        let slot = self.consumed % self.size;
+        if self.consumed >= self.size {
+            let leaving = self.buffer[slot] as usize;
+            self.counts[leaving] -= 1;
+            if self.counts[leaving] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.buffer[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
+        }
+        self.consumed += 1;
+
+        Window {
+            end: self.consumed,
+            len: self.consumed.min(self.size),
+            distinct: self.distinct,
+        }
+    }
+
+    /// Empty the window
+    pub fn clear(&mut self) {
+        self.consumed = 0;
+        self.counts = [0; 256];
+        self.distinct = 0;
+    }
+}
+
+/// Slides a window of fixed size over a byte stream, see `SlidingDistinct`
+pub struct DistinctWindow<I> {
+    inner: I,
+    window: SlidingDistinct,
+}
+
+impl<I> DistinctWindow<I> {
+    pub fn new(inner: I, size: usize) -> Self {
+        DistinctWindow {
+            inner,
+            window: SlidingDistinct::new(size),
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for DistinctWindow<I> {
+    type Item = Window;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.inner.next()?;
+        Some(self.window.push(byte))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub trait DistinctWindowExt: Iterator<Item = u8> + Sized {
+    fn distinct_window(self, size: usize) -> DistinctWindow<Self> {
+        DistinctWindow::new(self, size)
+    }
+}
+
+impl<I: Iterator<Item = u8>> DistinctWindowExt for I {}
+
+/// The offset right after the first `size` consecutive bytes that are all different
+pub fn find_distinct_run(bytes: impl Iterator<Item = u8>, size: usize) -> Option<usize> {
+    bytes
+        .distinct_window(size)
+        .find(|w| w.len == size && w.is_all_distinct())
+        .map(|w| w.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::{HashSet, VecDeque},
+        time::Instant,
+    };
+
+    #[test]
+    fn test_window_counts() {
+        let windows = b"abcab"
+            .iter()
+            .copied()
+            .distinct_window(3)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows.iter().map(|w| w.distinct).collect::<Vec<_>>(),
+            vec![1, 2, 3, 3, 3]
+        );
+        assert_eq!(windows[1].len, 2);
+        assert!(windows[1].is_all_distinct());
+
+        assert_eq!(
+            b"aabbaabb".iter().copied().distinct_window(4).last(),
+            Some(Window {
+                end: 8,
+                len: 4,
+                distinct: 2
+            })
+        );
+        assert_eq!(
+            find_distinct_run(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb".iter().copied(), 14),
+            Some(19)
+        );
+        assert_eq!(find_distinct_run(b"aaaa".iter().copied(), 2), None);
+
+        let mut window = SlidingDistinct::new(2);
+        window.push(b'a');
+        window.clear();
+        assert_eq!(
+            window.push(b'a'),
+            Window {
+                end: 1,
+                len: 1,
+                distinct: 1
+            }
+        );
+    }
+
+    /// Compare against rebuilding a `HashSet` of the window for every byte.
+    /// Run with `cargo test --release --lib -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_distinct_window() {
+        const SIZE: usize = 14;
+        // A repeating pattern with too few different bytes, followed by a distinct run
+        let mut stream = b"abcdefghijklm".repeat(4 * 1024 * 1024 / 13);
+        stream.extend_from_slice(b"nopqrstuvwxyzA");
+
+        let start = Instant::now();
+        let mut window = VecDeque::with_capacity(SIZE);
+        let naive = stream.iter().position(|&b| {
+            if window.len() == SIZE {
+                window.pop_front();
+            }
+            window.push_back(b);
+            window.iter().collect::<HashSet<_>>().len() == SIZE
+        });
+        let naive_time = start.elapsed();
+
+        let start = Instant::now();
+        let counted = find_distinct_run(stream.iter().copied(), SIZE);
+        let counted_time = start.elapsed();
+
+        assert_eq!(naive.map(|idx| idx + 1), counted);
+        println!(
+            "{} MiB stream: HashSet window {:?}, DistinctWindow {:?} ({:.1}x)",
+            stream.len() / (1024 * 1024),
+            naive_time,
+            counted_time,
+            naive_time.as_secs_f64() / counted_time.as_secs_f64()
+        );
+    }
+}
@@ -3,6 +3,8 @@ use std::{
     ops::{Index, IndexMut},
 };
 
+use crate::{direction::Direction8, geometry::Point2};
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Field2D<T> {
     values: Vec<T>,
@@ -59,12 +61,15 @@ impl<T> Field2D<T> {
         self.width() * self.height()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn neighbors(&self, x: usize, y: usize) -> NeighborIter {
         NeighborIter {
             field_size: (self.width(), self.height()),
             pos: (x, y),
-            diag: false,
-            state: NeighborIterState::default(),
+            directions: NEIGHBOR_ORDER[..4].iter(),
         }
     }
 
@@ -72,8 +77,7 @@ impl<T> Field2D<T> {
         NeighborIter {
             field_size: (self.width(), self.height()),
             pos: (x, y),
-            diag: true,
-            state: NeighborIterState::default(),
+            directions: NEIGHBOR_ORDER.iter(),
         }
     }
 
@@ -109,6 +113,18 @@ impl<T> Field2D<T> {
             .enumerate()
             .map(|(idx, val)| ((idx % self.width(), idx / self.width()), val))
     }
+
+    /// A field of the same size with `f` applied to every entry
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Field2D<U> {
+        Field2D {
+            values: self.values.iter().map(f).collect(),
+            width: self.width,
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.values.chunks(self.width)
+    }
 }
 
 impl<T> Index<(usize, usize)> for Field2D<T> {
@@ -132,6 +148,20 @@ impl<T> IndexMut<(usize, usize)> for Field2D<T> {
     }
 }
 
+impl<T> Index<Point2<usize>> for Field2D<T> {
+    type Output = T;
+
+    fn index(&self, index: Point2<usize>) -> &Self::Output {
+        &self[(index.x, index.y)]
+    }
+}
+
+impl<T> IndexMut<Point2<usize>> for Field2D<T> {
+    fn index_mut(&mut self, index: Point2<usize>) -> &mut Self::Output {
+        &mut self[(index.x, index.y)]
+    }
+}
+
 impl<T> IntoIterator for Field2D<T> {
     type Item = T;
 
@@ -142,103 +172,35 @@ impl<T> IntoIterator for Field2D<T> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum NeighborIterState {
-    Right,
-    Down,
-    Left,
-    Up,
-    UpLeft,
-    UpRight,
-    DownRight,
-    DownLeft,
-    Done,
-}
-
-impl Default for NeighborIterState {
-    fn default() -> Self {
-        Self::Right
-    }
-}
+/// Order in which neighbors are reported, the orthogonal ones come first so that the plain
+/// neighborhood is just a prefix of the diagonal one
+const NEIGHBOR_ORDER: [Direction8; 8] = [
+    Direction8::Right,
+    Direction8::Down,
+    Direction8::Left,
+    Direction8::Up,
+    Direction8::UpLeft,
+    Direction8::UpRight,
+    Direction8::DownRight,
+    Direction8::DownLeft,
+];
 
 #[derive(Debug, Clone)]
 pub struct NeighborIter {
     field_size: (usize, usize),
     pos: (usize, usize),
-    diag: bool,
-    state: NeighborIterState,
-}
-
-impl NeighborIter {
-    fn cur(&self) -> Option<(usize, usize)> {
-        use NeighborIterState::*;
-        let dx: i32 = match self.state {
-            Right | UpRight | DownRight => 1,
-            Left | UpLeft | DownLeft => -1,
-            Up | Down => 0,
-            Done => return None,
-        };
-        if dx == -1 && self.pos.0 == 0 {
-            return None;
-        }
-        if dx == 1 && self.pos.0 == self.field_size.0 - 1 {
-            return None;
-        }
-
-        let dy: i32 = match self.state {
-            Up | UpLeft | UpRight => -1,
-            Down | DownLeft | DownRight => 1,
-            Left | Right => 0,
-            Done => return None,
-        };
-        if dy == -1 && self.pos.1 == 0 {
-            return None;
-        }
-        if dy == 1 && self.pos.1 == self.field_size.1 - 1 {
-            return None;
-        }
-
-        // TODO: The casting here is not nice
-        Some((
-            (self.pos.0 as i32 + dx) as usize,
-            (self.pos.1 as i32 + dy) as usize,
-        ))
-    }
-
-    fn advance(&mut self) {
-        use NeighborIterState::*;
-        self.state = match self.state {
-            Right => Down,
-            Down => Left,
-            Left => Up,
-            Up => {
-                if self.diag {
-                    UpLeft
-                } else {
-                    Done
-                }
-            }
-            UpLeft => UpRight,
-            UpRight => DownRight,
-            DownRight => DownLeft,
-            DownLeft => Done,
-            Done => Done,
-        }
-    }
+    directions: std::slice::Iter<'static, Direction8>,
 }
 
 impl Iterator for NeighborIter {
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.state != NeighborIterState::Done {
-            if let Some(v) = self.cur() {
-                self.advance();
-                return Some(v);
-            } else {
-                self.advance();
-            }
-        }
-        None
+        let origin = Point2::from_index(self.pos);
+        self.directions.by_ref().find_map(|dir| {
+            (origin + dir.delta())
+                .to_index()
+                .filter(|(x, y)| *x < self.field_size.0 && *y < self.field_size.1)
+        })
     }
 }
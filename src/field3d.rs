@@ -0,0 +1,417 @@
+use std::{
+    fmt::Display,
+    iter::repeat_with,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VoxelCoordinate {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+/// The six face-adjacent neighbor offsets
+pub const NEIGHBORHOOD_6: [VoxelCoordinate; 6] = [
+    VoxelCoordinate::new(1, 0, 0),
+    VoxelCoordinate::new(-1, 0, 0),
+    VoxelCoordinate::new(0, 1, 0),
+    VoxelCoordinate::new(0, -1, 0),
+    VoxelCoordinate::new(0, 0, 1),
+    VoxelCoordinate::new(0, 0, -1),
+];
+
+impl VoxelCoordinate {
+    pub const fn new(x: isize, y: isize, z: isize) -> Self {
+        VoxelCoordinate { x, y, z }
+    }
+
+    /// All offsets with every component in `-1..=1`, except for the origin itself.
+    /// `max_nonzero` limits how many components may differ from zero, which yields the 6-, 18- and
+    /// 26-neighborhoods for values 1, 2 and 3.
+    fn neighborhood_deltas(max_nonzero: usize) -> impl Iterator<Item = VoxelCoordinate> {
+        (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| Self::new(x, y, z))))
+            .filter(move |d| {
+                let nonzero = [d.x, d.y, d.z].iter().filter(|c| **c != 0).count();
+                nonzero > 0 && nonzero <= max_nonzero
+            })
+    }
+
+    /// Face-adjacent neighbors
+    pub fn neighbors(self) -> impl Iterator<Item = VoxelCoordinate> {
+        NEIGHBORHOOD_6.into_iter().map(move |d| self + d)
+    }
+
+    /// Face- and edge-adjacent neighbors
+    pub fn neighbors_18(self) -> impl Iterator<Item = VoxelCoordinate> {
+        Self::neighborhood_deltas(2).map(move |d| self + d)
+    }
+
+    /// Face-, edge- and corner-adjacent neighbors
+    pub fn neighbors_26(self) -> impl Iterator<Item = VoxelCoordinate> {
+        Self::neighborhood_deltas(3).map(move |d| self + d)
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum VoxelCoordinateParseError {
+    #[error("Could not parse number")]
+    InvalidNumber(#[from] std::num::ParseIntError),
+    #[error("Expected 3 coordinates, found {0}")]
+    WrongDimension(usize),
+}
+
+impl FromStr for VoxelCoordinate {
+    type Err = VoxelCoordinateParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords = s
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if coords.len() != 3 {
+            return Err(VoxelCoordinateParseError::WrongDimension(coords.len()));
+        }
+
+        Ok(VoxelCoordinate::new(coords[0], coords[1], coords[2]))
+    }
+}
+
+impl Display for VoxelCoordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl Add for VoxelCoordinate {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        VoxelCoordinate::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for VoxelCoordinate {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for VoxelCoordinate {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        VoxelCoordinate::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for VoxelCoordinate {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for VoxelCoordinate {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        VoxelCoordinate::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<isize> for VoxelCoordinate {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        VoxelCoordinate::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+/// An axis aligned box, both corners are inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: VoxelCoordinate,
+    pub max: VoxelCoordinate,
+}
+
+impl BoundingBox {
+    pub fn new(min: VoxelCoordinate, max: VoxelCoordinate) -> Self {
+        assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z);
+        BoundingBox { min, max }
+    }
+
+    pub fn from_point(point: VoxelCoordinate) -> Self {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// Build the smallest box containing all points, or `None` if there are no points
+    pub fn from_points(points: impl IntoIterator<Item = VoxelCoordinate>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let mut result = Self::from_point(points.next()?);
+        points.for_each(|p| result.include(p));
+        Some(result)
+    }
+
+    /// Grow the box so that it contains `point`
+    pub fn include(&mut self, point: VoxelCoordinate) {
+        self.min = VoxelCoordinate::new(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = VoxelCoordinate::new(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    /// Get a copy of this box that is extended by `margin` in every direction
+    pub fn grow(&self, margin: isize) -> Self {
+        let delta = VoxelCoordinate::new(margin, margin, margin);
+        BoundingBox::new(self.min - delta, self.max + delta)
+    }
+
+    pub fn contains(&self, point: &VoxelCoordinate) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Check whether `point` lies on one of the six faces of the box
+    pub fn is_on_boundary(&self, point: &VoxelCoordinate) -> bool {
+        self.contains(point)
+            && (point.x == self.min.x
+                || point.x == self.max.x
+                || point.y == self.min.y
+                || point.y == self.max.y
+                || point.z == self.min.z
+                || point.z == self.max.z)
+    }
+
+    pub fn width(&self) -> usize {
+        self.min.x.abs_diff(self.max.x) + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.min.y.abs_diff(self.max.y) + 1
+    }
+
+    pub fn depth(&self) -> usize {
+        self.min.z.abs_diff(self.max.z) + 1
+    }
+
+    pub fn volume(&self) -> usize {
+        self.width() * self.height() * self.depth()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = VoxelCoordinate> {
+        let (min, max) = (self.min, self.max);
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y)
+                .flat_map(move |y| (min.x..=max.x).map(move |x| VoxelCoordinate::new(x, y, z)))
+        })
+    }
+}
+
+/// A dense 3D grid covering a fixed bounding box.
+/// Since the box does not need to start at the origin, negative coordinates can be stored as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field3D<T> {
+    values: Vec<T>,
+    bounds: BoundingBox,
+}
+
+impl<T> Field3D<T>
+where
+    T: Default,
+{
+    pub fn new_empty(bounds: BoundingBox) -> Self {
+        Field3D {
+            values: repeat_with(Default::default)
+                .take(bounds.volume())
+                .collect(),
+            bounds,
+        }
+    }
+}
+
+impl<T> Field3D<T>
+where
+    T: Clone,
+{
+    pub fn new_with_value(bounds: BoundingBox, value: T) -> Self {
+        Field3D {
+            values: vec![value; bounds.volume()],
+            bounds,
+        }
+    }
+}
+
+impl Field3D<bool> {
+    /// Build a field that is just large enough to contain all given voxels, which are set to
+    /// `true`. `margin` adds additional empty layers around the voxels.
+    pub fn from_voxels(voxels: &[VoxelCoordinate], margin: isize) -> Option<Self> {
+        let bounds = BoundingBox::from_points(voxels.iter().copied())?.grow(margin);
+        let mut res = Self::new_with_value(bounds, false);
+        voxels.iter().for_each(|v| res[*v] = true);
+        Some(res)
+    }
+}
+
+impl<T> Field3D<T> {
+    pub fn bounds(&self) -> &BoundingBox {
+        &self.bounds
+    }
+
+    pub fn width(&self) -> usize {
+        self.bounds.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.bounds.height()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.bounds.depth()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn offset(&self, pos: &VoxelCoordinate) -> Option<usize> {
+        if self.bounds.contains(pos) {
+            let rel = *pos - self.bounds.min;
+            Some(rel.x as usize + self.width() * (rel.y as usize + self.height() * rel.z as usize))
+        } else {
+            None
+        }
+    }
+
+    fn position(&self, offset: usize) -> VoxelCoordinate {
+        let x = offset % self.width();
+        let y = (offset / self.width()) % self.height();
+        let z = offset / (self.width() * self.height());
+        self.bounds.min + VoxelCoordinate::new(x as isize, y as isize, z as isize)
+    }
+
+    pub fn contains(&self, pos: &VoxelCoordinate) -> bool {
+        self.bounds.contains(pos)
+    }
+
+    pub fn get(&self, pos: &VoxelCoordinate) -> Option<&T> {
+        self.offset(pos).map(|o| &self.values[o])
+    }
+
+    pub fn get_mut(&mut self, pos: &VoxelCoordinate) -> Option<&mut T> {
+        self.offset(pos).map(|o| &mut self.values[o])
+    }
+
+    /// Face-adjacent neighbors of `pos` that are inside of the field
+    pub fn neighbors(&self, pos: VoxelCoordinate) -> impl Iterator<Item = VoxelCoordinate> + '_ {
+        pos.neighbors().filter(|n| self.contains(n))
+    }
+
+    /// Face- and edge-adjacent neighbors of `pos` that are inside of the field
+    pub fn neighbors_18(&self, pos: VoxelCoordinate) -> impl Iterator<Item = VoxelCoordinate> + '_ {
+        pos.neighbors_18().filter(|n| self.contains(n))
+    }
+
+    /// All neighbors of `pos`, including diagonal ones, that are inside of the field
+    pub fn neighbors_26(&self, pos: VoxelCoordinate) -> impl Iterator<Item = VoxelCoordinate> + '_ {
+        pos.neighbors_26().filter(|n| self.contains(n))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.values.iter_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+
+    pub fn iter_with_position(&self) -> impl Iterator<Item = (VoxelCoordinate, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(idx, val)| (self.position(idx), val))
+    }
+}
+
+impl<T> Index<VoxelCoordinate> for Field3D<T> {
+    type Output = T;
+
+    fn index(&self, index: VoxelCoordinate) -> &Self::Output {
+        self.get(&index).expect("Coordinate outside of field")
+    }
+}
+
+impl<T> IndexMut<VoxelCoordinate> for Field3D<T> {
+    fn index_mut(&mut self, index: VoxelCoordinate) -> &mut Self::Output {
+        self.get_mut(&index).expect("Coordinate outside of field")
+    }
+}
+
+impl<T> IntoIterator for Field3D<T> {
+    type Item = T;
+
+    type IntoIter = <Vec<T> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighborhood_sizes() {
+        let origin = VoxelCoordinate::default();
+        assert_eq!(origin.neighbors().count(), 6);
+        assert_eq!(origin.neighbors_18().count(), 18);
+        assert_eq!(origin.neighbors_26().count(), 26);
+        assert!(origin.neighbors_26().all(|n| n != origin));
+    }
+
+    #[test]
+    fn test_field_indexing_with_offset_bounds() {
+        let voxels = [
+            VoxelCoordinate::new(-1, 2, 3),
+            VoxelCoordinate::new(2, -2, 3),
+        ];
+        let field = Field3D::from_voxels(&voxels, 1).unwrap();
+        assert_eq!(
+            *field.bounds(),
+            BoundingBox::new(
+                VoxelCoordinate::new(-2, -3, 2),
+                VoxelCoordinate::new(3, 3, 4)
+            )
+        );
+        assert_eq!(field.len(), 6 * 7 * 3);
+        assert_eq!(field.iter().filter(|v| **v).count(), 2);
+        assert!(field[VoxelCoordinate::new(-1, 2, 3)]);
+        assert_eq!(field.get(&VoxelCoordinate::new(4, 0, 3)), None);
+        assert!(field
+            .iter_with_position()
+            .all(|(pos, v)| *v == voxels.contains(&pos)));
+        assert_eq!(field.neighbors(VoxelCoordinate::new(-2, -3, 2)).count(), 3);
+    }
+}
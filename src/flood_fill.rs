@@ -0,0 +1,206 @@
+use std::ops::{Index, IndexMut};
+
+use crate::{
+    field2d::Field2D,
+    field3d::{Field3D, VoxelCoordinate},
+};
+
+/// Common interface of the dense grids so that the search algorithms below work on 2D and 3D data
+pub trait Grid: Index<Self::Position, Output = Self::Item> {
+    type Item;
+    type Position: Copy;
+    /// A grid of the same shape holding other values, used for the results of the searches
+    type Mapped<U>: Index<Self::Position, Output = U> + IndexMut<Self::Position>;
+
+    fn positions(&self) -> impl Iterator<Item = Self::Position> + '_;
+
+    /// Directly adjacent positions (no diagonals) that are part of the grid
+    fn adjacent(&self, pos: Self::Position) -> impl Iterator<Item = Self::Position> + '_;
+
+    fn is_boundary(&self, pos: Self::Position) -> bool;
+
+    fn map_with_value<U: Clone>(&self, value: U) -> Self::Mapped<U>;
+}
+
+impl<T> Grid for Field2D<T> {
+    type Item = T;
+    type Position = (usize, usize);
+    type Mapped<U> = Field2D<U>;
+
+    fn positions(&self) -> impl Iterator<Item = Self::Position> + '_ {
+        self.iter_with_position().map(|(pos, _)| pos)
+    }
+
+    fn adjacent(&self, pos: Self::Position) -> impl Iterator<Item = Self::Position> + '_ {
+        self.neighbors(pos.0, pos.1)
+    }
+
+    fn is_boundary(&self, pos: Self::Position) -> bool {
+        pos.0 == 0 || pos.1 == 0 || pos.0 == self.width() - 1 || pos.1 == self.height() - 1
+    }
+
+    fn map_with_value<U: Clone>(&self, value: U) -> Self::Mapped<U> {
+        Field2D::new_with_value(self.width(), self.height(), value)
+    }
+}
+
+impl<T> Grid for Field3D<T> {
+    type Item = T;
+    type Position = VoxelCoordinate;
+    type Mapped<U> = Field3D<U>;
+
+    fn positions(&self) -> impl Iterator<Item = Self::Position> + '_ {
+        self.bounds().iter()
+    }
+
+    fn adjacent(&self, pos: Self::Position) -> impl Iterator<Item = Self::Position> + '_ {
+        self.neighbors(pos)
+    }
+
+    fn is_boundary(&self, pos: Self::Position) -> bool {
+        self.bounds().is_on_boundary(&pos)
+    }
+
+    fn map_with_value<U: Clone>(&self, value: U) -> Self::Mapped<U> {
+        Field3D::new_with_value(*self.bounds(), value)
+    }
+}
+
+/// Mark every cell that can be reached from one of the `starts` by only walking over cells for
+/// which `passable` holds. Start cells that are not passable are ignored.
+pub fn flood_fill<G, F>(
+    grid: &G,
+    starts: impl IntoIterator<Item = G::Position>,
+    mut passable: F,
+) -> G::Mapped<bool>
+where
+    G: Grid,
+    F: FnMut(&G::Item) -> bool,
+{
+    let mut visited = grid.map_with_value(false);
+    let mut remaining = Vec::new();
+
+    for start in starts {
+        if !visited[start] && passable(&grid[start]) {
+            visited[start] = true;
+            remaining.push(start);
+        }
+    }
+
+    while let Some(current) = remaining.pop() {
+        for neighbor in grid.adjacent(current) {
+            if !visited[neighbor] && passable(&grid[neighbor]) {
+                visited[neighbor] = true;
+                remaining.push(neighbor);
+            }
+        }
+    }
+
+    visited
+}
+
+/// Mark every passable cell that is connected to the outer boundary of the grid
+pub fn reachable_from_boundary<G, F>(grid: &G, passable: F) -> G::Mapped<bool>
+where
+    G: Grid,
+    F: FnMut(&G::Item) -> bool,
+{
+    let boundary = grid
+        .positions()
+        .filter(|pos| grid.is_boundary(*pos))
+        .collect::<Vec<_>>();
+    flood_fill(grid, boundary, passable)
+}
+
+#[derive(Debug, Clone)]
+pub struct Components<M> {
+    /// The component index of every cell, `None` for cells that were not part of any component
+    pub labels: M,
+    /// The number of cells in each component, indexed by component label
+    pub sizes: Vec<usize>,
+}
+
+impl<M> Components<M> {
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// The label and size of the largest component, the lowest label wins ties
+    pub fn largest(&self) -> Option<(usize, usize)> {
+        self.sizes
+            .iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, size)| *size)
+    }
+}
+
+/// Group all cells for which `include` holds into connected components.
+/// Labels are assigned in the order in which the grid positions are visited.
+pub fn label_components<G, F>(grid: &G, mut include: F) -> Components<G::Mapped<Option<usize>>>
+where
+    G: Grid,
+    F: FnMut(&G::Item) -> bool,
+{
+    let mut labels = grid.map_with_value(None);
+    let mut sizes = Vec::new();
+    let mut remaining = Vec::new();
+
+    for start in grid.positions() {
+        if labels[start].is_some() || !include(&grid[start]) {
+            continue;
+        }
+
+        let label = sizes.len();
+        let mut size = 0;
+        labels[start] = Some(label);
+        remaining.push(start);
+
+        while let Some(current) = remaining.pop() {
+            size += 1;
+            for neighbor in grid.adjacent(current) {
+                if labels[neighbor].is_none() && include(&grid[neighbor]) {
+                    labels[neighbor] = Some(label);
+                    remaining.push(neighbor);
+                }
+            }
+        }
+
+        sizes.push(size);
+    }
+
+    Components { labels, sizes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_field(rows: &[&str]) -> Field2D<bool> {
+        Field2D::parse(rows.iter(), |row| row.chars().map(|c| c == '#')).unwrap()
+    }
+
+    #[test]
+    fn test_components_2d() {
+        let field = parse_field(&["##..#", "#..##", "..#..", "##..#"]);
+        let components = label_components(&field, |v| *v);
+        assert_eq!(components.sizes, vec![3, 3, 1, 2, 1]);
+        assert_eq!(components.largest(), Some((0, 3)));
+        assert_eq!(components.labels[(4, 1)], Some(1));
+        assert_eq!(components.labels[(2, 0)], None);
+    }
+
+    #[test]
+    fn test_reachable_from_boundary_2d() {
+        let field = parse_field(&["#####", "#...#", "#.#.#", "###.#"]);
+        let outside = reachable_from_boundary(&field, |wall| !wall);
+        assert!(outside[(3, 3)]);
+        assert!(outside[(1, 1)]);
+        assert!(!outside[(0, 0)]);
+
+        let enclosed = parse_field(&["#####", "#...#", "#.#.#", "#####"]);
+        let outside = reachable_from_boundary(&enclosed, |wall| !wall);
+        assert!(outside.iter().all(|v| !v));
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use num::Signed;
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+/// Differences between points use the same representation as the points themselves
+pub type Vector2<T> = Point2<T>;
+
+impl<T> Point2<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T> Point2<T>
+where
+    T: Copy + PartialOrd + Sub<Output = T>,
+{
+    fn abs_diff(a: T, b: T) -> T {
+        if a > b {
+            a - b
+        } else {
+            b - a
+        }
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> T
+    where
+        T: Add<Output = T>,
+    {
+        Self::abs_diff(self.x, other.x) + Self::abs_diff(self.y, other.y)
+    }
+
+    /// The number of king moves between both points
+    pub fn chebyshev_distance(&self, other: &Self) -> T {
+        let dx = Self::abs_diff(self.x, other.x);
+        let dy = Self::abs_diff(self.y, other.y);
+        if dx > dy {
+            dx
+        } else {
+            dy
+        }
+    }
+}
+
+impl<T> Point2<T>
+where
+    T: Signed + Copy,
+{
+    /// Reduce each component to -1, 0 or 1
+    pub fn signum(&self) -> Self {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn abs(&self) -> Self {
+        Point2::new(self.x.abs(), self.y.abs())
+    }
+}
+
+impl Point2<isize> {
+    /// Convert a `Field2D` index into a point
+    pub fn from_index(index: (usize, usize)) -> Self {
+        Point2::new(index.0 as isize, index.1 as isize)
+    }
+
+    /// Convert this point into a `Field2D` index, if it does not have negative components
+    pub fn to_index(self) -> Option<(usize, usize)> {
+        Some((self.x.try_into().ok()?, self.y.try_into().ok()?))
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from(value: (T, T)) -> Self {
+        Point2::new(value.0, value.1)
+    }
+}
+
+impl<T> From<Point2<T>> for (T, T) {
+    fn from(value: Point2<T>) -> Self {
+        (value.x, value.y)
+    }
+}
+
+impl<T: Display> Display for Point2<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point2<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Point2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: Add<Output = T> + Copy> AddAssign for Point2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point2<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Point2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: Sub<Output = T> + Copy> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point2<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Point2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_and_distances() {
+        let a = Point2::new(3isize, -2);
+        let b = Point2::new(-1isize, 4);
+        assert_eq!(a + b, Point2::new(2, 2));
+        assert_eq!(a - b, Point2::new(4, -6));
+        assert_eq!(-a, Point2::new(-3, 2));
+        assert_eq!(a * 3, Point2::new(9, -6));
+        assert_eq!((b - a).signum(), Point2::new(-1, 1));
+        assert_eq!(a.manhattan_distance(&b), 10);
+        assert_eq!(a.chebyshev_distance(&b), 6);
+        assert_eq!(
+            Point2::new(2usize, 7).manhattan_distance(&Point2::new(5, 1)),
+            9
+        );
+    }
+
+    #[test]
+    fn test_index_conversion() {
+        assert_eq!(Point2::from_index((4, 2)), Point2::new(4, 2));
+        assert_eq!(Point2::new(4isize, 2).to_index(), Some((4, 2)));
+        assert_eq!(Point2::new(-1isize, 2).to_index(), None);
+    }
+}
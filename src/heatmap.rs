@@ -0,0 +1,155 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+use thiserror::Error;
+
+use crate::field2d::Field2D;
+
+#[derive(Error, Debug)]
+pub enum ImageFormatParseError {
+    #[error("Unknown image format '{0}', expected pgm, ppm or ansi")]
+    UnknownFormat(String),
+}
+
+/// The ways a heatmap can be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary greyscale Netpbm image
+    Pgm,
+    /// Binary colour Netpbm image
+    Ppm,
+    /// Coloured blocks for a terminal with true colour support
+    Ansi,
+}
+
+impl FromStr for ImageFormat {
+    type Err = ImageFormatParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(ImageFormat::Pgm),
+            "ppm" => Ok(ImageFormat::Ppm),
+            "ansi" => Ok(ImageFormat::Ansi),
+            _ => Err(ImageFormatParseError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Colour ramp from black over red and yellow to white
+    pub fn heat(intensity: u8) -> Self {
+        let i = intensity as usize * 3;
+        let channel = |offset: usize| i.saturating_sub(offset).min(255) as u8;
+        Rgb(channel(0), channel(255), channel(510))
+    }
+}
+
+/// Scale the values of a field linearly so that the smallest one becomes 0 and the largest one
+/// 255. A field where all values are equal maps to 0 everywhere.
+pub fn normalize<T, F: Fn(&T) -> f64>(field: &Field2D<T>, value: F) -> Field2D<u8> {
+    let values = field.map(value);
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    if max > min {
+        values.map(|v| ((v - min) / (max - min) * 255.0).round() as u8)
+    } else {
+        values.map(|_| 0)
+    }
+}
+
+/// Write a field of intensities as a heatmap. Greyscale images use the intensities directly,
+/// the colour formats go through `Rgb::heat`.
+pub fn render<W: Write>(
+    intensities: &Field2D<u8>,
+    format: ImageFormat,
+    mut out: W,
+) -> io::Result<()> {
+    match format {
+        ImageFormat::Pgm => {
+            write!(
+                out,
+                "P5\n{} {}\n255\n",
+                intensities.width(),
+                intensities.height()
+            )?;
+            for row in intensities.rows() {
+                out.write_all(row)?;
+            }
+        }
+        ImageFormat::Ppm => {
+            write!(
+                out,
+                "P6\n{} {}\n255\n",
+                intensities.width(),
+                intensities.height()
+            )?;
+            for &i in intensities.iter() {
+                let Rgb(r, g, b) = Rgb::heat(i);
+                out.write_all(&[r, g, b])?;
+            }
+        }
+        ImageFormat::Ansi => {
+            // Two blocks per cell so that cells come out roughly square
+            for row in intensities.rows() {
+                for &i in row {
+                    let Rgb(r, g, b) = Rgb::heat(i);
+                    write!(out, "\x1b[48;2;{};{};{}m  ", r, g, b)?;
+                }
+                writeln!(out, "\x1b[0m")?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let field = Field2D::parse([[0usize, 5], [10, 5]].into_iter(), |row| row).unwrap();
+        let intensities = normalize(&field, |&v| v as f64);
+        assert_eq!(
+            intensities.iter().copied().collect::<Vec<_>>(),
+            [0, 128, 255, 128]
+        );
+        assert_eq!(
+            normalize(&field, |_| 1.0)
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            [0, 0, 0, 0]
+        );
+
+        assert_eq!(Rgb::heat(0), Rgb(0, 0, 0));
+        assert_eq!(Rgb::heat(128), Rgb(255, 129, 0));
+        assert_eq!(Rgb::heat(255), Rgb(255, 255, 255));
+
+        let mut pgm = Vec::new();
+        render(&intensities, ImageFormat::Pgm, &mut pgm).unwrap();
+        assert_eq!(pgm, b"P5\n2 2\n255\n\x00\x80\xff\x80");
+
+        let mut ppm = Vec::new();
+        render(&intensities, ImageFormat::Ppm, &mut ppm).unwrap();
+        assert_eq!(ppm.len(), b"P6\n2 2\n255\n".len() + 12);
+
+        let mut ansi = Vec::new();
+        render(&intensities, ImageFormat::Ansi, &mut ansi).unwrap();
+        let ansi = String::from_utf8(ansi).unwrap();
+        assert_eq!(
+            ansi.lines().next(),
+            Some("\x1b[48;2;0;0;0m  \x1b[48;2;255;129;0m  \x1b[0m")
+        );
+        assert_eq!(ansi.lines().count(), 2);
+
+        assert!("svg".parse::<ImageFormat>().is_err());
+    }
+}
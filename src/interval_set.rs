@@ -0,0 +1,254 @@
+use std::{
+    cmp,
+    ops::{Add, Range, Sub},
+};
+
+use num::Zero;
+
+/// A set of values stored as sorted, disjoint half-open ranges.
+/// Overlapping or touching ranges are merged on insertion, so every gap between two stored ranges
+/// contains at least one value that is not part of the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+impl<T> IntervalSet<T>
+where
+    T: Ord + Copy,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a range to the set, empty ranges are ignored
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut merged = range;
+        let mut result = Vec::with_capacity(self.ranges.len() + 1);
+        let mut inserted = false;
+        for r in self.ranges.drain(..) {
+            if r.end < merged.start {
+                result.push(r);
+            } else if merged.end < r.start {
+                if !inserted {
+                    result.push(merged.clone());
+                    inserted = true;
+                }
+                result.push(r);
+            } else {
+                merged = cmp::min(r.start, merged.start)..cmp::max(r.end, merged.end);
+            }
+        }
+        if !inserted {
+            result.push(merged);
+        }
+        self.ranges = result;
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.ranges.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= *value);
+        self.ranges.get(idx).is_some_and(|r| r.contains(value))
+    }
+
+    /// Check whether every value of `range` is part of the set
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        range.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|r| r.start <= range.start && r.end >= range.end)
+    }
+
+    /// Check whether at least one value of `range` is part of the set
+    pub fn overlaps(&self, range: &Range<T>) -> bool {
+        self.ranges
+            .iter()
+            .any(|r| r.start < range.end && range.start < r.end)
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.ranges.iter().all(|r| self.contains_range(r))
+    }
+
+    /// The smallest range containing the whole set
+    pub fn span(&self) -> Option<Range<T>> {
+        Some(self.ranges.first()?.start..self.ranges.last()?.end)
+    }
+
+    /// The ranges between consecutive entries of the set
+    pub fn gaps(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.ranges
+            .iter()
+            .zip(self.ranges.iter().skip(1))
+            .map(|(a, b)| a.end..b.start)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges
+            .iter()
+            .chain(other.ranges.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let overlap = cmp::max(a.start, b.start)..cmp::min(a.end, b.end);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for r in &self.ranges {
+            let mut start = r.start;
+            // Skip everything in other that ends before the current range starts
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < r.end {
+                let cut = &other.ranges[k];
+                if cut.start > start {
+                    ranges.push(start..cut.start);
+                }
+                start = cmp::max(start, cut.end);
+                k += 1;
+            }
+            if start < r.end {
+                ranges.push(start..r.end);
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    /// All values of `bounds` that are not part of the set
+    pub fn complement_within(&self, bounds: Range<T>) -> Self {
+        IntervalSet::from(bounds).difference(self)
+    }
+}
+
+impl<T> IntervalSet<T>
+where
+    T: Ord + Copy + Zero + Add<Output = T> + Sub<Output = T>,
+{
+    /// The total number of values in the set
+    pub fn len(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |acc, r| acc + (r.end - r.start))
+    }
+}
+
+impl<T> From<Range<T>> for IntervalSet<T>
+where
+    T: Ord + Copy,
+{
+    fn from(value: Range<T>) -> Self {
+        let mut res = Self::new();
+        res.insert(value);
+        res
+    }
+}
+
+impl<T> FromIterator<Range<T>> for IntervalSet<T>
+where
+    T: Ord + Copy,
+{
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut sorted = iter
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|r| r.start);
+
+        let mut ranges: Vec<Range<T>> = Vec::with_capacity(sorted.len());
+        for r in sorted {
+            match ranges.last_mut() {
+                Some(last) if last.end >= r.start => last.end = cmp::max(last.end, r.end),
+                _ => ranges.push(r),
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merging() {
+        let mut set = IntervalSet::new();
+        set.insert(5..8);
+        set.insert(1..3);
+        set.insert(3..4);
+        set.insert(10..10);
+        assert_eq!(set.ranges(), &[1..4, 5..8]);
+        set.insert(2..6);
+        assert_eq!(set.ranges().len(), 1);
+        assert_eq!(set.span(), Some(1..8));
+        assert_eq!(
+            [7..9, 1..2, 8..12, 0..1]
+                .into_iter()
+                .collect::<IntervalSet<_>>(),
+            IntervalSet {
+                ranges: vec![0..2, 7..12]
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = [0..5, 10..15, 20..25]
+            .into_iter()
+            .collect::<IntervalSet<isize>>();
+        let b = [3..12, 14..21].into_iter().collect::<IntervalSet<isize>>();
+
+        assert_eq!(a.union(&b), IntervalSet::from(0..25));
+        assert_eq!(a.intersection(&b).ranges(), &[3..5, 10..12, 14..15, 20..21]);
+        assert_eq!(a.difference(&b).ranges(), &[0..3, 12..14, 21..25]);
+        assert_eq!(b.difference(&a).ranges(), &[5..10, 15..20]);
+        assert_eq!(a.len(), 15);
+        assert_eq!(a.gaps().collect::<Vec<_>>(), vec![5..10, 15..20]);
+        assert_eq!(a.complement_within(-2..12).ranges(), &[-2..0, 5..10]);
+        assert!(a.contains(&4) && !a.contains(&5) && a.contains(&24));
+        assert!(a.contains_range(&(11..15)) && !a.contains_range(&(4..6)));
+        assert!(a.overlaps(&(4..6)) && !a.overlaps(&(5..10)));
+        assert!(a.union(&b).is_superset(&a) && !a.is_superset(&b));
+    }
+}
@@ -5,7 +5,15 @@ use std::path::Path;
 use std::str::FromStr;
 use thiserror::Error;
 
+pub mod direction;
+pub mod distinct_window;
 pub mod field2d;
+pub mod field3d;
+pub mod flood_fill;
+pub mod geometry;
+pub mod heatmap;
+pub mod interval_set;
+pub mod top_k;
 
 #[derive(Error, Debug)]
 pub enum InputError<T> {
@@ -22,7 +30,7 @@ where
 {
     BufReader::new(input).lines().map(|maybe_line| {
         maybe_line
-            .map_err(|e| InputError::IoError(e))
+            .map_err(InputError::IoError)
             .and_then(|l| Ok(l.parse()?))
     })
 }
@@ -68,7 +76,7 @@ where
                     }
                 }
                 None => {
-                    if group.len() > 0 {
+                    if !group.is_empty() {
                         return Some(group);
                     } else {
                         return None;
@@ -83,8 +91,8 @@ pub fn stream_file_blocks<P: AsRef<Path>>(
     path: P,
 ) -> std::io::Result<impl Iterator<Item = Vec<String>>> {
     let file = File::open(path)?;
-    let lines = BufReader::new(file).lines().filter_map(Result::ok);
-    Ok(BlockCollector::new(lines, |line: &String| line.len() == 0))
+    let lines = BufReader::new(file).lines().map_while(Result::ok);
+    Ok(BlockCollector::new(lines, |line: &String| line.is_empty()))
 }
 
 pub mod test_helpers {
@@ -109,4 +117,28 @@ pub mod test_helpers {
         write!(file, "{}", inp).expect("Could not write to file");
         (dir, filepath)
     }
+
+    /// Deterministic pseudo random numbers for generating larger test inputs
+    pub struct TestRng(u64);
+
+    impl TestRng {
+        pub fn new(seed: u64) -> Self {
+            TestRng(seed)
+        }
+
+        /// Linear congruential generator with the constants of Knuth's MMIX. Only the upper bits
+        /// of the state are returned, the lower ones repeat with short periods.
+        pub fn next_u32(&mut self) -> u32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 32) as u32
+        }
+
+        /// A number in `0..n`
+        pub fn below(&mut self, n: usize) -> usize {
+            (self.next_u32() as u64 % n as u64) as usize
+        }
+    }
 }
@@ -0,0 +1,136 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+/// Collects the `k` largest items pushed into it.
+/// Internally this is a min-heap of at most `k` elements, so the smallest retained item can be
+/// evicted in `O(log k)` whenever a larger one arrives.
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        TopK {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+        } else if self.threshold().is_some_and(|min| item > *min) {
+            self.heap.pop();
+            self.heap.push(Reverse(item));
+        }
+    }
+
+    /// The smallest item that is currently retained
+    pub fn threshold(&self) -> Option<&T> {
+        self.heap.peek().map(|Reverse(item)| item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The retained items, largest first
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // Sorting the reversed items ascending yields the original items in descending order
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| self.push(item));
+    }
+}
+
+/// Orders items only by their key, so that items don't need to be comparable themselves
+struct Keyed<K, T> {
+    key: K,
+    item: T,
+}
+
+impl<K: Ord, T> PartialEq for Keyed<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Ord, T> Eq for Keyed<K, T> {}
+
+impl<K: Ord, T> PartialOrd for Keyed<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for Keyed<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// The `k` largest items, largest first
+pub fn top_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    let mut collector = TopK::new(k);
+    collector.extend(items);
+    collector.into_sorted_vec()
+}
+
+/// The `k` items with the largest keys, largest first
+pub fn top_k_by_key<T, K, F>(items: impl IntoIterator<Item = T>, k: usize, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    top_k(
+        items.into_iter().map(|item| Keyed {
+            key: f(&item),
+            item,
+        }),
+        k,
+    )
+    .into_iter()
+    .map(|keyed| keyed.item)
+    .collect()
+}
+
+/// The `k` smallest items, smallest first
+pub fn bottom_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    top_k(items.into_iter().map(Reverse), k)
+        .into_iter()
+        .map(|Reverse(item)| item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection() {
+        let values = [5, 1, 9, 3, 9, 7, 2];
+        assert_eq!(top_k(values, 3), vec![9, 9, 7]);
+        assert_eq!(bottom_k(values, 2), vec![1, 2]);
+        assert_eq!(top_k(values, 10).len(), values.len());
+        assert!(top_k(values, 0).is_empty());
+
+        let words = ["pear", "fig", "banana", "kiwi"];
+        assert_eq!(top_k_by_key(words, 2, |w| w.len()), vec!["banana", "pear"]);
+    }
+}